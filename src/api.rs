@@ -0,0 +1,316 @@
+use axum::extract::State;
+use axum::http::{header, Request, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::json;
+use serenity::client::Context;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+use crate::metrics::record_shard_connected;
+use crate::store::errors::StoreError;
+use crate::store::BotStore;
+
+pub mod settings;
+
+type ErrorMessage = String;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+/// What the gateway handler has seen so far, reported on `/healthz` and `/readyz`
+#[derive(Default)]
+pub struct Health {
+    // Keyed by shard id, only shards that reported in at least once
+    shards: Mutex<BTreeMap<u64, bool>>,
+    cache_ready: AtomicBool,
+    // Shards this process runs, 0 until it is known
+    expected_shards: AtomicU64,
+    // Shards whose guilds went through `setup_guild`
+    setup_shards: Mutex<BTreeSet<u64>>,
+    // Any shard's context, they share the cache and the client data
+    context: Mutex<Option<Context>>,
+}
+
+impl Health {
+    pub fn set_shard_connected(&self, shard_id: u64, connected: bool) {
+        if let Ok(mut shards) = self.shards.lock() {
+            shards.insert(shard_id, connected);
+        }
+        record_shard_connected(shard_id, connected);
+    }
+
+    fn shards(&self) -> BTreeMap<u64, bool> {
+        self.shards
+            .lock()
+            .map(|shards| shards.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn set_cache_ready(&self) {
+        self.cache_ready.store(true, Ordering::Relaxed);
+    }
+
+    /// How many shards this process runs, `/readyz` waits for all of them
+    pub fn set_expected_shards(&self, count: u64) {
+        self.expected_shards.store(count, Ordering::Relaxed);
+    }
+
+    /// The total shard count from a `ready`, for `SHARDS=auto` where it isn't known up front.
+    /// Every auto shard runs in this process, a count set before is kept
+    pub fn set_total_shards(&self, total: u64) {
+        let _ =
+            self.expected_shards
+                .compare_exchange(0, total, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// The guilds of `shard_id` went through `setup_guild` after its `ready`
+    pub fn set_setup_complete(&self, shard_id: u64) {
+        self.setup_shards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(shard_id);
+    }
+
+    fn setup_complete(&self) -> bool {
+        let expected = self.expected_shards.load(Ordering::Relaxed);
+        let setup = self
+            .setup_shards
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len() as u64;
+
+        expected > 0 && setup >= expected
+    }
+
+    /// Keep a `Context` around to recheck a guild after the API changed its settings
+    pub fn set_context(&self, ctx: &Context) {
+        *self.context.lock().unwrap_or_else(PoisonError::into_inner) = Some(ctx.clone());
+    }
+
+    fn context(&self) -> Option<Context> {
+        self.context
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct ApiState {
+    store: Arc<dyn BotStore>,
+    health: Arc<Health>,
+    token: Option<Arc<str>>,
+}
+
+#[derive(Error, Debug)]
+pub enum ApiError {
+    #[error("Missing or invalid API token")]
+    Unauthorized,
+
+    #[error("The admin API is disabled, set API_TOKEN to enable it")]
+    Disabled,
+
+    #[error("{0}")]
+    BadRequest(ErrorMessage),
+
+    #[error("Redis: {0}")]
+    RedisError(ErrorMessage),
+}
+
+impl From<StoreError> for ApiError {
+    fn from(error: StoreError) -> Self {
+        ApiError::RedisError(error.to_string())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Disabled => StatusCode::FORBIDDEN,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::RedisError(_) => StatusCode::SERVICE_UNAVAILABLE,
+        };
+
+        (status, Json(json!({ "error": self.to_string() }))).into_response()
+    }
+}
+
+#[derive(Serialize)]
+struct HealthReport {
+    gateway_connected: bool,
+    shards: BTreeMap<u64, bool>,
+    redis_reachable: bool,
+    cache_ready: bool,
+}
+
+impl HealthReport {
+    fn is_healthy(&self) -> bool {
+        self.gateway_connected && self.redis_reachable && self.cache_ready
+    }
+}
+
+async fn health_report(state: &ApiState) -> HealthReport {
+    let shards = state.health.shards();
+
+    HealthReport {
+        // Every shard this process runs has to be up, not just the first one
+        gateway_connected: !shards.is_empty() && shards.values().all(|connected| *connected),
+        shards,
+        redis_reachable: state.store.ping().await.is_ok(),
+        cache_ready: state.health.cache_ready.load(Ordering::Relaxed),
+    }
+}
+
+async fn healthz(State(state): State<ApiState>) -> (StatusCode, Json<HealthReport>) {
+    let report = health_report(&state).await;
+    let status = match report.is_healthy() {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(report))
+}
+
+async fn readyz(State(state): State<ApiState>) -> StatusCode {
+    let report = health_report(&state).await;
+    match report.is_healthy() && state.health.setup_complete() {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+// Every `/api` route needs `Authorization: Bearer <API_TOKEN>`
+async fn authenticate<B>(
+    State(state): State<ApiState>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, ApiError> {
+    let token = match &state.token {
+        Some(token) => token,
+        None => return Err(ApiError::Disabled),
+    };
+
+    let given = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match given {
+        Some(given) if tokens_match(given, token) => Ok(next.run(request).await),
+        _ => Err(ApiError::Unauthorized),
+    }
+}
+
+// Compare every byte of the token whatever was given, padding a short guess, so the time
+// taken gives away neither how much of the token was right nor how long it is
+fn tokens_match(given: &str, token: &str) -> bool {
+    let given = given.as_bytes();
+    let diff = token.bytes().enumerate().fold(0, |diff, (index, b)| {
+        diff | (given.get(index).copied().unwrap_or(0) ^ b)
+    });
+
+    diff == 0 && given.len() == token.len()
+}
+
+fn router(state: ApiState) -> Router {
+    let api = Router::new()
+        .route("/guilds/:guild_id/games", get(settings::list_games))
+        .route(
+            "/guilds/:guild_id/games/:role_id",
+            put(settings::add_game).delete(settings::remove_game),
+        )
+        .route("/guilds/:guild_id/admins", get(settings::list_admins))
+        .route(
+            "/guilds/:guild_id/admins/:user_id",
+            put(settings::add_admin).delete(settings::remove_admin),
+        )
+        .route(
+            "/guilds/:guild_id/log-channels",
+            get(settings::get_log_channels).put(settings::set_log_channels),
+        )
+        .route_layer(middleware::from_fn_with_state(state.clone(), authenticate));
+
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .nest("/api", api)
+        .with_state(state)
+}
+
+/// Serve the health checks and the admin API on `API_ADDR`, localhost only by default
+pub async fn serve(store: Arc<dyn BotStore>, health: Arc<Health>) {
+    let addr = env::var("API_ADDR").unwrap_or_else(|_| DEFAULT_ADDR.to_string());
+    let addr: SocketAddr = match addr.parse() {
+        Ok(addr) => addr,
+        Err(_) => {
+            error!("API_ADDR `{}` is not a valid address, API disabled", addr);
+            return;
+        }
+    };
+
+    let token: Option<Arc<str>> = env::var("API_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .map(Arc::from);
+    if token.is_none() {
+        warn!("API_TOKEN is not set, only the health checks are served");
+    }
+
+    let state = ApiState {
+        store,
+        health,
+        token,
+    };
+
+    let server = match axum::Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(why) => {
+            error!("Could not serve the API on {}: {}", addr, why);
+            return;
+        }
+    };
+
+    info!("Serving the API on http://{}", addr);
+    if let Err(why) = server.serve(router(state).into_make_service()).await {
+        error!("API server error: {}", why);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn equal_tokens_match() {
+        assert!(tokens_match("secret-token", "secret-token"));
+    }
+
+    #[test]
+    fn different_tokens_do_not_match() {
+        assert!(!tokens_match("secret-tokex", "secret-token"));
+        assert!(!tokens_match("Secret-token", "secret-token"));
+    }
+
+    #[test]
+    fn tokens_of_another_length_do_not_match() {
+        assert!(!tokens_match("secret", "secret-token"));
+        assert!(!tokens_match("secret-token-and-more", "secret-token"));
+        // A short guess is padded with 0, matching the padding isn't enough
+        assert!(!tokens_match("ab", "ab\0"));
+    }
+
+    #[test]
+    fn an_empty_token_does_not_match() {
+        assert!(!tokens_match("", "secret-token"));
+    }
+}
@@ -0,0 +1,139 @@
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use serenity::model::id::GuildId;
+
+use super::{ApiError, ApiState};
+use crate::events::start_up::check_guild;
+use crate::readiness::record_report;
+
+// Ids are sent as strings, they don't fit in a JavaScript number
+#[derive(Serialize, Deserialize)]
+pub struct LogChannels {
+    major: Option<String>,
+    minor: Option<String>,
+}
+
+// Recheck the guild like `/config` does, so the features it disabled follow the new settings.
+// Nothing to refresh before the first shard is ready, setting up the guild checks it then
+async fn refresh_readiness(state: &ApiState, guild_id: GuildId) {
+    if let Some(ctx) = state.health.context() {
+        let report = check_guild(&ctx, state.store.as_ref(), guild_id).await;
+        record_report(&ctx, report).await;
+    }
+}
+
+pub async fn list_games(
+    State(state): State<ApiState>,
+    Path(guild_id): Path<u64>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    Ok(Json(state.store.list_games(GuildId(guild_id)).await?))
+}
+
+pub async fn add_game(
+    State(state): State<ApiState>,
+    Path((guild_id, role_id)): Path<(u64, u64)>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .add_game(GuildId(guild_id), role_id.to_string())
+        .await?;
+    refresh_readiness(&state, GuildId(guild_id)).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn remove_game(
+    State(state): State<ApiState>,
+    Path((guild_id, role_id)): Path<(u64, u64)>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .remove_game(GuildId(guild_id), role_id.to_string())
+        .await?;
+    refresh_readiness(&state, GuildId(guild_id)).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_admins(
+    State(state): State<ApiState>,
+    Path(guild_id): Path<u64>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    Ok(Json(state.store.list_admins(GuildId(guild_id)).await?))
+}
+
+pub async fn add_admin(
+    State(state): State<ApiState>,
+    Path((guild_id, user_id)): Path<(u64, u64)>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .add_admin(GuildId(guild_id), user_id.to_string())
+        .await?;
+    refresh_readiness(&state, GuildId(guild_id)).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn remove_admin(
+    State(state): State<ApiState>,
+    Path((guild_id, user_id)): Path<(u64, u64)>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .remove_admin(GuildId(guild_id), user_id.to_string())
+        .await?;
+    refresh_readiness(&state, GuildId(guild_id)).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn get_log_channels(
+    State(state): State<ApiState>,
+    Path(guild_id): Path<u64>,
+) -> Result<Json<LogChannels>, ApiError> {
+    let guild_id = GuildId(guild_id);
+
+    Ok(Json(LogChannels {
+        major: state.store.get_major_log_channel(guild_id).await?,
+        minor: state.store.get_minor_log_channel(guild_id).await?,
+    }))
+}
+
+/// Set both log channels, `null` resets a channel
+pub async fn set_log_channels(
+    State(state): State<ApiState>,
+    Path(guild_id): Path<u64>,
+    Json(channels): Json<LogChannels>,
+) -> Result<Json<LogChannels>, ApiError> {
+    let guild_id = GuildId(guild_id);
+
+    for channel_id in [&channels.major, &channels.minor].into_iter().flatten() {
+        if channel_id.parse::<u64>().is_err() {
+            return Err(ApiError::BadRequest(format!(
+                "`{}` is not a channel id",
+                channel_id
+            )));
+        }
+    }
+
+    match &channels.major {
+        Some(channel_id) => {
+            state
+                .store
+                .set_major_log_channel(guild_id, channel_id.clone())
+                .await?
+        }
+        None => state.store.reset_major_log_channel(guild_id).await?,
+    };
+    match &channels.minor {
+        Some(channel_id) => {
+            state
+                .store
+                .set_minor_log_channel(guild_id, channel_id.clone())
+                .await?
+        }
+        None => state.store.reset_minor_log_channel(guild_id).await?,
+    };
+    refresh_readiness(&state, guild_id).await;
+
+    Ok(Json(channels))
+}
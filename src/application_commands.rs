@@ -1,14 +1,20 @@
 use crate::events::application_command::CommandDataBundle;
-
-use self::errors::CommandError;
-use serenity::builder::CreateApplicationCommands;
+use crate::interaction_error::InteractionError;
+use crate::permissions::PermissionLevel;
+use async_trait::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommands};
+use serenity::model::application::command::Command;
 
 pub mod add_admin;
 pub mod add_game;
-pub mod errors;
+pub mod bot_status;
+pub mod config;
 pub mod get_user_id;
 pub mod list_admins;
 pub mod list_games;
+pub mod log_routes;
+pub mod options;
+pub mod permissions;
 pub mod ping;
 pub mod prune;
 pub mod remove_admin;
@@ -20,68 +26,120 @@ pub mod test_log_channel;
 pub mod test_modal;
 pub mod test_multiple_select;
 pub mod test_single_select;
+pub mod transfer_ownership;
 pub mod utils;
+pub mod vc;
+pub mod voice_history;
+pub mod voice_hubs;
+
+/// A slash command, everything needed to register it with Discord and run it
+#[async_trait]
+pub trait SlashCommand: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
 
-pub fn guild_commands_reg(
-    commands: &mut CreateApplicationCommands,
-) -> &mut CreateApplicationCommands {
-    // Test Commands
-    commands.create_application_command(test_give_roles::setup());
-    commands.create_application_command(test_log_channel::setup());
-    commands.create_application_command(test_button_message::setup());
-    commands.create_application_command(test_single_select::setup());
-    commands.create_application_command(test_multiple_select::setup());
-    commands.create_application_command(test_modal::setup());
+    /// Add the command's options, most commands have none
+    fn options(&self, _command: &mut CreateApplicationCommand) {}
 
-    // Supported Games Commands
-    commands.create_application_command(add_game::setup());
-    commands.create_application_command(list_games::setup());
-    commands.create_application_command(remove_game::setup());
+    /// Suggestions as `(name, value)` for `option` while `partial` is typed,
+    /// only asked for options registered with `set_autocomplete(true)`
+    fn autocomplete(&self, _option: &str, _partial: &str) -> Vec<(String, String)> {
+        Vec::new()
+    }
 
-    // UI Component Commands
-    commands.create_application_command(setup_pick_games_modal::setup());
+    /// The level a member needs to run the command
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Admin
+    }
 
-    // Admin Commands
-    commands.create_application_command(add_admin::setup());
-    commands.create_application_command(list_admins::setup());
-    commands.create_application_command(remove_admin::setup());
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError>;
 
-    // Util Commands
-    commands.create_application_command(prune::setup());
-    commands.create_application_command(get_user_id::setup())
+    fn register<'a>(
+        &self,
+        command: &'a mut CreateApplicationCommand,
+    ) -> &'a mut CreateApplicationCommand {
+        command.name(self.name()).description(self.description());
+        self.options(command);
+        command
+    }
 }
 
-pub async fn execute_command(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    let command_name = data_bundle.interaction.data.name.as_str();
+/// The single list of commands, registration and dispatch both read from it
+pub struct CommandRegistry {
+    commands: &'static [&'static dyn SlashCommand],
+}
 
-    match command_name {
-        // Test commands
-        "test-give-roles" => test_give_roles::execute(data_bundle).await,
-        "test-log-channel" => test_log_channel::execute(data_bundle).await,
-        "test-button-message" => test_button_message::execute(data_bundle).await,
-        "test-single-select" => test_single_select::execute(data_bundle).await,
-        "test-multiple-select" => test_multiple_select::execute(data_bundle).await,
-        "test-modal" => test_modal::execute(data_bundle).await,
-        "ping" => ping::execute(data_bundle).await,
+impl CommandRegistry {
+    pub fn find(&self, name: &str) -> Option<&'static dyn SlashCommand> {
+        self.commands
+            .iter()
+            .find(|command| command.name() == name)
+            .copied()
+    }
 
-        // UI Component Commands
-        "setup-pick-games-modal" => setup_pick_games_modal::execute(data_bundle).await,
+    pub fn register<'a>(
+        &self,
+        commands: &'a mut CreateApplicationCommands,
+    ) -> &'a mut CreateApplicationCommands {
+        for command in self.commands {
+            commands.create_application_command(|builder| command.register(builder));
+        }
+        commands
+    }
 
-        // Admin commands
-        "add-admin" => add_admin::execute(data_bundle).await,
-        "list-admins" => list_admins::execute(data_bundle).await,
-        "remove-admin" => remove_admin::execute(data_bundle).await,
+    /// Names of commands Discord has registered that nothing here handles
+    pub fn unhandled<'a>(&self, registered: &'a [Command]) -> Vec<&'a str> {
+        registered
+            .iter()
+            .map(|command| command.name.as_str())
+            .filter(|name| self.find(name).is_none())
+            .collect()
+    }
+}
 
+pub static GUILD_COMMANDS: CommandRegistry = CommandRegistry {
+    commands: &[
+        // Test Commands
+        &test_give_roles::TestGiveRoles,
+        &test_log_channel::TestLogChannel,
+        &test_button_message::TestButtonMessage,
+        &test_single_select::TestSingleSelect,
+        &test_multiple_select::TestMultipleSelect,
+        &test_modal::TestModal,
         // Supported Games Commands
-        "add-game" => add_game::execute(data_bundle).await,
-        "list-games" => list_games::execute(data_bundle).await,
-        "remove-game" => remove_game::execute(data_bundle).await,
+        &add_game::AddGame,
+        &list_games::ListGames,
+        &remove_game::RemoveGame,
+        // UI Component Commands
+        &setup_pick_games_modal::SetupPickGamesModal,
+        // Admin Commands
+        &add_admin::AddAdmin,
+        &list_admins::ListAdmins,
+        &remove_admin::RemoveAdmin,
+        &permissions::Permissions,
+        &transfer_ownership::TransferOwnership,
+        // Config Commands
+        &config::Config,
+        &log_routes::LogRoutes,
+        &bot_status::BotStatus,
+        &voice_hubs::VoiceHubs,
+        // Util Commands
+        &prune::Prune,
+        &get_user_id::GetUserId,
+        &voice_history::VoiceHistory,
+        &vc::Vc,
+    ],
+};
 
-        // Util commands
-        "prune" => prune::execute(data_bundle).await,
-        "get-user-id" => get_user_id::execute(data_bundle).await,
+pub static GLOBAL_COMMANDS: CommandRegistry = CommandRegistry {
+    commands: &[&ping::Ping],
+};
 
-        // No match
-        _ => Ok("Command removed or not implemented".to_string()),
-    }
+pub fn find_command(name: &str) -> Option<&'static dyn SlashCommand> {
+    GUILD_COMMANDS
+        .find(name)
+        .or_else(|| GLOBAL_COMMANDS.find(name))
 }
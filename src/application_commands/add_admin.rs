@@ -1,55 +1,49 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
-use crate::redis_client::{self, add_admin};
+use crate::interaction_error::InteractionError;
+use crate::store::get_store;
+use async_trait::async_trait;
 use serenity::builder::CreateApplicationCommand;
 use serenity::model::application::command::CommandOptionType;
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+use serenity::model::user::User;
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct AddAdmin;
 
-    let command_interaction = &data_bundle.interaction;
-    let options = command_interaction.data.options.get(0);
-    let options = match options {
-        Some(x) => x,
-        None => return Err(CommandError::ArgumentMissing("Add Admin".to_string())),
-    };
+#[async_trait]
+impl SlashCommand for AddAdmin {
+    fn name(&self) -> &'static str {
+        "add-admin"
+    }
 
-    let options = options.resolved.as_ref();
-    let options = match options {
-        Some(x) => x,
-        None => {
-            return Err(CommandError::UnresolvedData(
-                "Add Admin".to_string(),
-                "Expected user object".to_string(),
-            ))
-        }
-    };
+    fn description(&self) -> &'static str {
+        "Add user as an admin"
+    }
 
-    if let CommandDataOptionValue::User(user, _member) = options {
-        let mut connection = redis_client::connect();
-        match add_admin(&mut connection, user.id.to_string()) {
-            Ok(_) => Ok(format!("{} has been added to the admin list", user.tag())),
-            Err(_) => Err(CommandError::RedisError("add_admin() failed".to_string())),
-        }
-    } else {
-        Err(CommandError::Other(
-            "Please provide a valid user".to_string(),
-        ))
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("id")
+                .description("The user to add")
+                .kind(CommandOptionType::User)
+                .required(true)
+        });
     }
-}
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("add-admin")
-            .description("Add user as an admin")
-            .create_option(|option| {
-                option
-                    .name("id")
-                    .description("The user to add")
-                    .kind(CommandOptionType::User)
-                    .required(true)
-            })
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let user = data_bundle.option::<User>("id")?;
+
+        let store = get_store(&data_bundle.ctx).await?;
+        match store.add_admin(guild_id, user.id.to_string()).await {
+            Ok(_) => Ok(format!("{} has been added to the admin list", user.tag())),
+            Err(_) => Err(InteractionError::RedisError(
+                "add_admin() failed".to_string(),
+            )),
+        }
     }
 }
@@ -1,55 +1,49 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
-use crate::redis_client::{self, add_game};
+use crate::interaction_error::InteractionError;
+use crate::store::get_store;
+use async_trait::async_trait;
 use serenity::builder::CreateApplicationCommand;
 use serenity::model::application::command::CommandOptionType;
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+use serenity::model::guild::Role;
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct AddGame;
 
-    let command_interaction = &data_bundle.interaction;
-    let options = command_interaction.data.options.get(0);
-    let options = match options {
-        Some(x) => x,
-        None => return Err(CommandError::ArgumentMissing("Add Game".to_string())),
-    };
+#[async_trait]
+impl SlashCommand for AddGame {
+    fn name(&self) -> &'static str {
+        "add-game"
+    }
 
-    let options = options.resolved.as_ref();
-    let options = match options {
-        Some(x) => x,
-        None => {
-            return Err(CommandError::UnresolvedData(
-                "Add Game".to_string(),
-                "Expected role object".to_string(),
-            ))
-        }
-    };
+    fn description(&self) -> &'static str {
+        "Add a game role to the list of games"
+    }
 
-    if let CommandDataOptionValue::Role(role) = options {
-        let mut connection = redis_client::connect();
-        match add_game(&mut connection, role.id.to_string()) {
-            Ok(_) => Ok(format!("{} has been added to the game list", role.name)),
-            Err(_) => Err(CommandError::RedisError("add_game() failed".to_string())),
-        }
-    } else {
-        Err(CommandError::Other(
-            "Please provide a valid role".to_string(),
-        ))
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("game-role")
+                .description("The role to add")
+                .kind(CommandOptionType::Role)
+                .required(true)
+        });
     }
-}
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("add-game")
-            .description("Add a game role to the list of games")
-            .create_option(|option| {
-                option
-                    .name("game-role")
-                    .description("The role to add")
-                    .kind(CommandOptionType::Role)
-                    .required(true)
-            })
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let role = data_bundle.option::<Role>("game-role")?;
+
+        let store = get_store(&data_bundle.ctx).await?;
+        match store.add_game(guild_id, role.id.to_string()).await {
+            Ok(_) => Ok(format!("{} has been added to the game list", role.name)),
+            Err(_) => Err(InteractionError::RedisError(
+                "add_game() failed".to_string(),
+            )),
+        }
     }
 }
@@ -0,0 +1,38 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::events::start_up::check_guild;
+use crate::interaction_error::InteractionError;
+use crate::readiness::record_report;
+use crate::store::get_store;
+use async_trait::async_trait;
+
+pub struct BotStatus;
+
+#[async_trait]
+impl SlashCommand for BotStatus {
+    fn name(&self) -> &'static str {
+        "bot-status"
+    }
+
+    fn description(&self) -> &'static str {
+        "Check what the bot can and can't do in this server"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let ctx = &data_bundle.ctx;
+        let store = get_store(ctx).await?;
+
+        // Run the checks again so fixing the config re-enables features without a restart
+        let report = check_guild(ctx, store.as_ref(), guild_id).await;
+        let content = format!("**Bot status**\n{}", report.render());
+        record_report(ctx, report).await;
+
+        Ok(content)
+    }
+}
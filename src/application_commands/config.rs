@@ -0,0 +1,285 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::events::start_up::check_guild;
+use crate::interaction_error::InteractionError;
+use crate::readiness::record_report;
+use crate::store::{get_store, BotStore};
+use async_trait::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::channel::ChannelType;
+use serenity::model::guild::Role;
+use serenity::model::id::GuildId;
+
+#[derive(Clone, Copy)]
+enum Setting {
+    FollowerRole,
+    MajorLogChannel,
+    MinorLogChannel,
+    BotAdminRole,
+}
+
+impl Setting {
+    const ALL: [Setting; 4] = [
+        Setting::FollowerRole,
+        Setting::MajorLogChannel,
+        Setting::MinorLogChannel,
+        Setting::BotAdminRole,
+    ];
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|setting| setting.name() == name)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Setting::FollowerRole => "follower-role",
+            Setting::MajorLogChannel => "major-log-channel",
+            Setting::MinorLogChannel => "minor-log-channel",
+            Setting::BotAdminRole => "bot-admin-role",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Setting::FollowerRole => "Follower role",
+            Setting::MajorLogChannel => "Major log channel",
+            Setting::MinorLogChannel => "Minor log channel",
+            Setting::BotAdminRole => "Bot admin role",
+        }
+    }
+
+    fn is_channel(&self) -> bool {
+        matches!(self, Setting::MajorLogChannel | Setting::MinorLogChannel)
+    }
+
+    fn mention(&self, id: &str) -> String {
+        if self.is_channel() {
+            format!("<#{}>", id)
+        } else {
+            format!("<@&{}>", id)
+        }
+    }
+
+    async fn get(
+        &self,
+        store: &dyn BotStore,
+        guild_id: GuildId,
+    ) -> Result<Option<String>, InteractionError> {
+        let value = match self {
+            Setting::FollowerRole => store.get_follower_role(guild_id).await?,
+            Setting::MajorLogChannel => store.get_major_log_channel(guild_id).await?,
+            Setting::MinorLogChannel => store.get_minor_log_channel(guild_id).await?,
+            Setting::BotAdminRole => store.get_bot_role(guild_id).await?,
+        };
+        Ok(value)
+    }
+
+    async fn set(
+        &self,
+        store: &dyn BotStore,
+        guild_id: GuildId,
+        id: String,
+    ) -> Result<(), InteractionError> {
+        match self {
+            Setting::FollowerRole => store.set_follower_role(guild_id, id).await?,
+            Setting::MajorLogChannel => store.set_major_log_channel(guild_id, id).await?,
+            Setting::MinorLogChannel => store.set_minor_log_channel(guild_id, id).await?,
+            Setting::BotAdminRole => store.set_bot_role(guild_id, id).await?,
+        };
+        Ok(())
+    }
+
+    async fn reset(&self, store: &dyn BotStore, guild_id: GuildId) -> Result<(), InteractionError> {
+        match self {
+            Setting::FollowerRole => store.reset_follower_role(guild_id).await?,
+            Setting::MajorLogChannel => store.reset_major_log_channel(guild_id).await?,
+            Setting::MinorLogChannel => store.reset_minor_log_channel(guild_id).await?,
+            Setting::BotAdminRole => store.reset_bot_role(guild_id).await?,
+        };
+        Ok(())
+    }
+}
+
+pub struct Config;
+
+#[async_trait]
+impl SlashCommand for Config {
+    fn name(&self) -> &'static str {
+        "config"
+    }
+
+    fn description(&self) -> &'static str {
+        "View and change the bot configuration for this server"
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command
+            .create_option(|option| {
+                option
+                    .name("set")
+                    .description("Change a setting")
+                    .kind(CommandOptionType::SubCommandGroup);
+                for setting in Setting::ALL {
+                    option.add_sub_option(set_option(setting));
+                }
+                option
+            })
+            .create_option(|option| {
+                option
+                    .name("get")
+                    .description("Show the current settings")
+                    .kind(CommandOptionType::SubCommand)
+            })
+            .create_option(|option| {
+                option
+                    .name("reset")
+                    .description("Clear a setting")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|setting_option| {
+                        setting_option
+                            .name("setting")
+                            .description("The setting to clear")
+                            .kind(CommandOptionType::String)
+                            .required(true);
+                        for setting in Setting::ALL {
+                            setting_option.add_string_choice(setting.label(), setting.name());
+                        }
+                        setting_option
+                    })
+            });
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let store = get_store(&data_bundle.ctx).await?;
+
+        let subcommand = match data_bundle.interaction.data.options.get(0) {
+            Some(x) => x,
+            None => return Err(InteractionError::ArgumentMissing("Config".to_string())),
+        };
+
+        let content = match subcommand.name.as_str() {
+            "set" => set(store.as_ref(), guild_id, data_bundle, subcommand).await?,
+            "get" => return get(store.as_ref(), guild_id).await,
+            "reset" => reset(store.as_ref(), guild_id, data_bundle).await?,
+            _ => {
+                return Err(InteractionError::UnresolvedData(
+                    "Config".to_string(),
+                    format!("Unknown subcommand `{}`", subcommand.name),
+                ))
+            }
+        };
+
+        // Features are gated on the last report, so a fixed setting takes effect right away
+        let ctx = &data_bundle.ctx;
+        let report = check_guild(ctx, store.as_ref(), guild_id).await;
+        record_report(ctx, report).await;
+
+        Ok(content)
+    }
+}
+
+async fn set(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    data_bundle: &CommandDataBundle,
+    subcommand: &CommandDataOption,
+) -> Result<String, InteractionError> {
+    // `/config set <setting> <value>`, the setting is itself a subcommand
+    let setting_option = match subcommand.options.get(0) {
+        Some(x) => x,
+        None => return Err(InteractionError::ArgumentMissing("Config Set".to_string())),
+    };
+
+    let setting = match Setting::from_name(&setting_option.name) {
+        Some(x) => x,
+        None => {
+            return Err(InteractionError::UnresolvedData(
+                "Config Set".to_string(),
+                format!("Unknown setting `{}`", setting_option.name),
+            ))
+        }
+    };
+
+    let id = if setting.is_channel() {
+        let channel = data_bundle.channel_option("channel", &[ChannelType::Text])?;
+        channel.id.to_string()
+    } else {
+        data_bundle.option::<Role>("role")?.id.to_string()
+    };
+
+    let mention = setting.mention(&id);
+    setting.set(store, guild_id, id).await?;
+
+    Ok(format!("{} set to {}", setting.label(), mention))
+}
+
+async fn get(store: &dyn BotStore, guild_id: GuildId) -> Result<String, InteractionError> {
+    let mut content = "".to_string();
+    for setting in Setting::ALL {
+        let value = match setting.get(store, guild_id).await? {
+            Some(id) => setting.mention(&id),
+            None => "not configured".to_string(),
+        };
+        content.push_str(&format!("{}: {}\n", setting.label(), value));
+    }
+
+    Ok(content)
+}
+
+async fn reset(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    data_bundle: &CommandDataBundle,
+) -> Result<String, InteractionError> {
+    let setting = Setting::from_name(&data_bundle.option::<String>("setting")?);
+
+    let setting = match setting {
+        Some(x) => x,
+        None => {
+            return Err(InteractionError::Rejected(
+                "Please provide a valid setting".to_string(),
+            ))
+        }
+    };
+
+    setting.reset(store, guild_id).await?;
+
+    Ok(format!("{} is no longer configured", setting.label()))
+}
+
+fn set_option(setting: Setting) -> CreateApplicationCommandOption {
+    let mut option = CreateApplicationCommandOption::default();
+    option
+        .name(setting.name())
+        .description(format!("Set the {}", setting.label().to_lowercase()))
+        .kind(CommandOptionType::SubCommand);
+
+    if setting.is_channel() {
+        option.create_sub_option(|value| {
+            value
+                .name("channel")
+                .description("The channel to use")
+                .kind(CommandOptionType::Channel)
+                .channel_types(&[ChannelType::Text])
+                .required(true)
+        });
+    } else {
+        option.create_sub_option(|value| {
+            value
+                .name("role")
+                .description("The role to use")
+                .kind(CommandOptionType::Role)
+                .required(true)
+        });
+    }
+
+    option
+}
@@ -1,50 +1,46 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::permissions::PermissionLevel;
+use async_trait::async_trait;
 use serenity::builder::CreateApplicationCommand;
 use serenity::model::application::command::CommandOptionType;
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
-
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
-
-    let command_interaction = &data_bundle.interaction;
-    let options = command_interaction.data.options.get(0);
-    let options = match options {
-        Some(x) => x,
-        None => return Err(CommandError::ArgumentMissing("Get User ID".to_string())),
-    };
-
-    let options = options.resolved.as_ref();
-    let options = match options {
-        Some(x) => x,
-        None => {
-            return Err(CommandError::UnresolvedData(
-                "Get User ID".to_string(),
-                "Expected user object".to_string(),
-            ))
-        }
-    };
-
-    if let CommandDataOptionValue::User(user, _member) = options {
-        Ok(format!("{}'s id is {}", user.tag(), user.id))
-    } else {
-        Err(CommandError::Other(
-            "Please provide a valid user".to_string(),
-        ))
+use serenity::model::user::User;
+
+pub struct GetUserId;
+
+#[async_trait]
+impl SlashCommand for GetUserId {
+    fn name(&self) -> &'static str {
+        "get-user-id"
     }
-}
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("get-user-id")
-            .description("Get a user id")
-            .create_option(|option| {
-                option
-                    .name("id")
-                    .description("The user to lookup")
-                    .kind(CommandOptionType::User)
-                    .required(true)
-            })
+    fn description(&self) -> &'static str {
+        "Get a user id"
+    }
+
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Moderator
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("id")
+                .description("The user to lookup")
+                .kind(CommandOptionType::User)
+                .required(true)
+        });
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let user = data_bundle.option::<User>("id")?;
+
+        Ok(format!("{}'s id is {}", user.tag(), user.id))
     }
 }
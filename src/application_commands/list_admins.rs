@@ -1,44 +1,67 @@
 use crate::{
-    application_commands::errors::CommandError,
-    events::application_command::CommandDataBundle,
-    redis_client::{self, list_admins},
+    application_commands::SlashCommand, events::application_command::CommandDataBundle,
+    interaction_error::InteractionError, permissions::list_owners, store::get_store,
 };
-use serenity::{builder::CreateApplicationCommand, model::prelude::UserId};
-
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
-
-    let ctx = &data_bundle.ctx;
-    let mut connection = redis_client::connect();
-    let admins = match list_admins(&mut connection) {
-        Ok(x) => x,
-        Err(error) => return Err(CommandError::RedisError(error.to_string())),
-    };
-    let mut content = "".to_string();
-    for admin in admins {
-        let user_id = match admin.parse::<u64>() {
-            Ok(x) => x,
-            Err(error) => return Err(CommandError::Other(error.to_string())),
-        };
-        let user = match UserId(user_id).to_user(&ctx).await {
+use async_trait::async_trait;
+use serenity::model::prelude::UserId;
+
+pub struct ListAdmins;
+
+#[async_trait]
+impl SlashCommand for ListAdmins {
+    fn name(&self) -> &'static str {
+        "list-admins"
+    }
+
+    fn description(&self) -> &'static str {
+        "List all bot admins"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+
+        let ctx = &data_bundle.ctx;
+        let store = get_store(ctx).await?;
+        let owners = list_owners(ctx, store.as_ref(), guild_id).await?;
+        let mut admins = match store.list_admins(guild_id).await {
             Ok(x) => x,
-            Err(error) => {
-                return Err(CommandError::UnresolvedData(
-                    "list-admins".to_string(),
-                    error.to_string(),
-                ))
-            }
+            Err(error) => return Err(InteractionError::RedisError(error.to_string())),
         };
-        content.push_str(&format!("{}#{}\n", user.name, user.discriminator));
-    }
 
-    Ok(content)
-}
+        // Owners are admins whether or not they are in the admin list
+        admins.retain(|admin| !owners.contains(admin));
+        let admins = owners
+            .iter()
+            .map(|owner| (owner, true))
+            .chain(admins.iter().map(|admin| (admin, false)));
+
+        let mut content = "".to_string();
+        for (admin, is_owner) in admins {
+            let user_id = match admin.parse::<u64>() {
+                Ok(x) => x,
+                Err(error) => return Err(InteractionError::Other(error.to_string())),
+            };
+            let user = match UserId(user_id).to_user(&ctx).await {
+                Ok(x) => x,
+                Err(error) => {
+                    return Err(InteractionError::UnresolvedData(
+                        "list-admins".to_string(),
+                        error.to_string(),
+                    ))
+                }
+            };
+            if is_owner {
+                content.push_str(&format!("{}#{} (owner)\n", user.name, user.discriminator));
+            } else {
+                content.push_str(&format!("{}#{}\n", user.name, user.discriminator));
+            }
+        }
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("list-admins")
-            .description("List all bot admins")
+        Ok(content)
     }
 }
@@ -0,0 +1,303 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::log_channel::digest::{self, MAX_WINDOW_MINUTES};
+use crate::log_channel::routing::DefaultChannel;
+use crate::log_channel::LogType;
+use crate::store::{get_store, BotStore};
+use async_trait::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::channel::ChannelType;
+use serenity::model::id::GuildId;
+
+pub struct LogRoutes;
+
+#[async_trait]
+impl SlashCommand for LogRoutes {
+    fn name(&self) -> &'static str {
+        "log-routes"
+    }
+
+    fn description(&self) -> &'static str {
+        "Choose which channels each type of log is posted to"
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command
+            .add_option(route_option(
+                "set",
+                "Post a log type to this channel only",
+                true,
+            ))
+            .add_option(route_option(
+                "add",
+                "Also post a log type to this channel",
+                true,
+            ))
+            .add_option(route_option(
+                "remove",
+                "Stop posting a log type to this channel",
+                true,
+            ))
+            .add_option(route_option("mute", "Stop posting a log type", false))
+            .add_option(route_option(
+                "reset",
+                "Post a log type to its default log channel again",
+                false,
+            ))
+            .add_option(digest_option())
+            .create_option(|option| {
+                option
+                    .name("list")
+                    .description("Show where every log type is posted")
+                    .kind(CommandOptionType::SubCommand)
+            });
+    }
+
+    fn autocomplete(&self, option: &str, partial: &str) -> Vec<(String, String)> {
+        if option != "type" {
+            return Vec::new();
+        }
+
+        let partial = partial.to_lowercase();
+        LogType::ALL
+            .into_iter()
+            .filter(|log_type| {
+                log_type.label().to_lowercase().contains(&partial)
+                    || log_type.name().contains(&partial)
+            })
+            .map(|log_type| (log_type.label().to_string(), log_type.name().to_string()))
+            .collect()
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let store = get_store(&data_bundle.ctx).await?;
+
+        let subcommand = match data_bundle.interaction.data.options.get(0) {
+            Some(x) => x,
+            None => return Err(InteractionError::ArgumentMissing("Log Routes".to_string())),
+        };
+
+        if subcommand.name == "list" {
+            return list(store.as_ref(), guild_id).await;
+        }
+
+        let log_type = match LogType::from_name(&data_bundle.option::<String>("type")?) {
+            Some(x) => x,
+            None => {
+                return Err(InteractionError::Rejected(
+                    "Please provide a valid log type".to_string(),
+                ))
+            }
+        };
+
+        if subcommand.name == "digest" {
+            let minutes = data_bundle.integer_option("minutes", 0..=MAX_WINDOW_MINUTES)?;
+            return digest(store.as_ref(), guild_id, log_type, minutes).await;
+        }
+
+        let mut channel_ids = current_route(store.as_ref(), guild_id, log_type).await?;
+
+        match subcommand.name.as_str() {
+            "set" => {
+                let channel = data_bundle.channel_option("channel", &[ChannelType::Text])?;
+                channel_ids = vec![channel.id.to_string()];
+            }
+            "add" => {
+                let channel = data_bundle.channel_option("channel", &[ChannelType::Text])?;
+                let channel_id = channel.id.to_string();
+                if !channel_ids.contains(&channel_id) {
+                    channel_ids.push(channel_id);
+                }
+            }
+            "remove" => {
+                let channel = data_bundle.channel_option("channel", &[ChannelType::Text])?;
+                let channel_id = channel.id.to_string();
+                channel_ids.retain(|id| *id != channel_id);
+            }
+            "mute" => channel_ids.clear(),
+            "reset" => {
+                store
+                    .reset_log_route(guild_id, log_type.name().to_string())
+                    .await?;
+                return Ok(match log_type.default_channel() {
+                    DefaultChannel::Off => format!("{} logs are off again", log_type.label()),
+                    default => format!(
+                        "{} logs are posted to the {} again",
+                        log_type.label(),
+                        default.label().to_lowercase()
+                    ),
+                });
+            }
+            _ => {
+                return Err(InteractionError::UnresolvedData(
+                    "Log Routes".to_string(),
+                    format!("Unknown subcommand `{}`", subcommand.name),
+                ))
+            }
+        }
+
+        store
+            .set_log_route(guild_id, log_type.name().to_string(), channel_ids.join(" "))
+            .await?;
+
+        Ok(format!(
+            "{} logs are now posted to {}",
+            log_type.label(),
+            mentions(&channel_ids)
+        ))
+    }
+}
+
+/// The channels `log_type` goes to now, starting from its default channel when it was never routed
+async fn current_route(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    log_type: LogType,
+) -> Result<Vec<String>, InteractionError> {
+    let routes = store.list_log_routes(guild_id).await?;
+
+    let channel_ids = match routes.get(log_type.name()) {
+        Some(channel_ids) => channel_ids
+            .split_whitespace()
+            .map(|id| id.to_string())
+            .collect(),
+        None => log_type
+            .default_channel()
+            .configured(store, guild_id)
+            .await?
+            .into_iter()
+            .collect(),
+    };
+
+    Ok(channel_ids)
+}
+
+async fn digest(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    log_type: LogType,
+    minutes: i64,
+) -> Result<String, InteractionError> {
+    if minutes == 0 {
+        store
+            .reset_log_digest(guild_id, log_type.name().to_string())
+            .await?;
+        digest::invalidate(guild_id);
+        return Ok(format!(
+            "{} logs are posted as they happen again",
+            log_type.label()
+        ));
+    }
+
+    store
+        .set_log_digest(guild_id, log_type.name().to_string(), minutes.to_string())
+        .await?;
+    digest::invalidate(guild_id);
+
+    Ok(format!(
+        "{} logs are now posted as one digest every {} minutes",
+        log_type.label(),
+        minutes
+    ))
+}
+
+async fn list(store: &dyn BotStore, guild_id: GuildId) -> Result<String, InteractionError> {
+    let routes = store.list_log_routes(guild_id).await?;
+    let digests = store.list_log_digests(guild_id).await?;
+
+    let mut content = "".to_string();
+    for log_type in LogType::ALL {
+        let route = match routes.get(log_type.name()) {
+            Some(channel_ids) => {
+                let channel_ids: Vec<String> = channel_ids
+                    .split_whitespace()
+                    .map(|id| id.to_string())
+                    .collect();
+                mentions(&channel_ids)
+            }
+            None => format!(
+                "{} (default)",
+                log_type.default_channel().label().to_lowercase()
+            ),
+        };
+        let digest = match digests.get(log_type.name()) {
+            Some(minutes) => format!(", digest every {} minutes", minutes),
+            None => "".to_string(),
+        };
+        content.push_str(&format!("{}: {}{}\n", log_type.label(), route, digest));
+    }
+
+    Ok(content)
+}
+
+fn mentions(channel_ids: &[String]) -> String {
+    match channel_ids.len() {
+        0 => "nowhere, muted".to_string(),
+        _ => channel_ids
+            .iter()
+            .map(|id| format!("<#{}>", id))
+            .collect::<Vec<String>>()
+            .join(" "),
+    }
+}
+
+fn digest_option() -> CreateApplicationCommandOption {
+    let mut option = route_option(
+        "digest",
+        "Post a log type as one summary every few minutes",
+        false,
+    );
+    option.create_sub_option(|minutes_option| {
+        minutes_option
+            .name("minutes")
+            .description("Minutes per digest, 0 posts every event as it happens")
+            .kind(CommandOptionType::Integer)
+            .min_int_value(0)
+            .max_int_value(MAX_WINDOW_MINUTES)
+            .required(true)
+    });
+
+    option
+}
+
+fn route_option(
+    name: &str,
+    description: &str,
+    with_channel: bool,
+) -> CreateApplicationCommandOption {
+    let mut option = CreateApplicationCommandOption::default();
+    option
+        .name(name)
+        .description(description)
+        .kind(CommandOptionType::SubCommand)
+        .create_sub_option(|type_option| {
+            // Autocomplete rather than choices, Discord allows at most 25 choices
+            type_option
+                .name("type")
+                .description("The type of log")
+                .kind(CommandOptionType::String)
+                .set_autocomplete(true)
+                .required(true)
+        });
+
+    if with_channel {
+        option.create_sub_option(|channel_option| {
+            channel_option
+                .name("channel")
+                .description("The channel to post to")
+                .kind(CommandOptionType::Channel)
+                .channel_types(&[ChannelType::Text])
+                .required(true)
+        });
+    }
+
+    option
+}
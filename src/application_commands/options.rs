@@ -0,0 +1,196 @@
+use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::application::interaction::application_command::{
+    CommandDataOption, CommandDataOptionValue,
+};
+use serenity::model::channel::{Attachment, ChannelType, PartialChannel};
+use serenity::model::guild::Role;
+use serenity::model::user::User;
+use std::ops::RangeInclusive;
+
+/// A type a resolved slash command option can be read as
+pub trait FromOption: Sized {
+    /// What the option should have been, e.g. "user", for error messages
+    const EXPECTED: &'static str;
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self>;
+}
+
+impl FromOption for User {
+    const EXPECTED: &'static str = "user";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::User(user, _member) => Some(user.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOption for Role {
+    const EXPECTED: &'static str = "role";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Role(role) => Some(role.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOption for PartialChannel {
+    const EXPECTED: &'static str = "channel";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Channel(channel) => Some(channel.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOption for Attachment {
+    const EXPECTED: &'static str = "attachment";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Attachment(attachment) => Some(attachment.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOption for String {
+    const EXPECTED: &'static str = "string";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl FromOption for i64 {
+    const EXPECTED: &'static str = "whole number";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Integer(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromOption for f64 {
+    const EXPECTED: &'static str = "number";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FromOption for bool {
+    const EXPECTED: &'static str = "true or false";
+
+    fn from_option(value: &CommandDataOptionValue) -> Option<Self> {
+        match value {
+            CommandDataOptionValue::Boolean(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Find `name` among `options`, looking inside whichever subcommand was used
+fn find_option<'a>(options: &'a [CommandDataOption], name: &str) -> Option<&'a CommandDataOption> {
+    options.iter().find_map(|option| match option.kind {
+        CommandOptionType::SubCommand | CommandOptionType::SubCommandGroup => {
+            find_option(&option.options, name)
+        }
+        _ if option.name == name => Some(option),
+        _ => None,
+    })
+}
+
+impl CommandDataBundle {
+    /// Read the required option `name`, e.g. `data_bundle.option::<User>("id")`
+    pub fn option<T: FromOption>(&self, name: &str) -> Result<T, InteractionError> {
+        match self.optional_option(name)? {
+            Some(value) => Ok(value),
+            None => Err(InteractionError::ArgumentMissing(
+                self.interaction.data.name.clone(),
+            )),
+        }
+    }
+
+    /// Read the option `name`, `None` when it wasn't given
+    pub fn optional_option<T: FromOption>(
+        &self,
+        name: &str,
+    ) -> Result<Option<T>, InteractionError> {
+        let option = match find_option(&self.interaction.data.options, name) {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let value = match option.resolved.as_ref() {
+            Some(x) => x,
+            None => {
+                return Err(InteractionError::UnresolvedData(
+                    self.interaction.data.name.clone(),
+                    format!("Expected `{}` to be a {}", name, T::EXPECTED),
+                ))
+            }
+        };
+
+        match T::from_option(value) {
+            Some(value) => Ok(Some(value)),
+            None => Err(InteractionError::UnresolvedData(
+                self.interaction.data.name.clone(),
+                format!("Expected `{}` to be a {}", name, T::EXPECTED),
+            )),
+        }
+    }
+
+    /// Read the required integer option `name`, it has to fall within `range`
+    pub fn integer_option(
+        &self,
+        name: &str,
+        range: RangeInclusive<i64>,
+    ) -> Result<i64, InteractionError> {
+        let value = self.option::<i64>(name)?;
+        if range.contains(&value) {
+            Ok(value)
+        } else {
+            Err(InteractionError::Rejected(format!(
+                "`{}` must be between {} and {}",
+                name,
+                range.start(),
+                range.end()
+            )))
+        }
+    }
+
+    /// Read the required channel option `name`, it has to be one of `kinds`
+    pub fn channel_option(
+        &self,
+        name: &str,
+        kinds: &[ChannelType],
+    ) -> Result<PartialChannel, InteractionError> {
+        let channel = self.option::<PartialChannel>(name)?;
+        if kinds.contains(&channel.kind) {
+            Ok(channel)
+        } else {
+            let kinds: Vec<&str> = kinds.iter().map(|kind| kind.name()).collect();
+            Err(InteractionError::Rejected(format!(
+                "`{}` must be a {} channel",
+                name,
+                kinds.join(" or ")
+            )))
+        }
+    }
+}
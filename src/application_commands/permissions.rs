@@ -0,0 +1,221 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::permissions::PermissionLevel;
+use crate::store::{get_store, BotStore};
+use async_trait::async_trait;
+use serenity::builder::{CreateApplicationCommand, CreateApplicationCommandOption};
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::guild::Role;
+use serenity::model::id::GuildId;
+use serenity::model::user::User;
+
+pub struct Permissions;
+
+#[async_trait]
+impl SlashCommand for Permissions {
+    fn name(&self) -> &'static str {
+        "permissions"
+    }
+
+    fn description(&self) -> &'static str {
+        "Grant permission levels to roles and users"
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command
+            .add_option(grant_option("grant-role", CommandOptionType::Role))
+            .add_option(grant_option("grant-user", CommandOptionType::User))
+            .add_option(revoke_option("revoke-role", CommandOptionType::Role))
+            .add_option(revoke_option("revoke-user", CommandOptionType::User))
+            .create_option(|option| {
+                option
+                    .name("list")
+                    .description("Show every granted permission level")
+                    .kind(CommandOptionType::SubCommand)
+            });
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let store = get_store(&data_bundle.ctx).await?;
+
+        let subcommand = match data_bundle.interaction.data.options.get(0) {
+            Some(x) => x,
+            None => return Err(InteractionError::ArgumentMissing("Permissions".to_string())),
+        };
+
+        match subcommand.name.as_str() {
+            "grant-role" | "grant-user" => {
+                let target = Target::from_bundle(data_bundle, &subcommand.name)?;
+                grant(store.as_ref(), guild_id, data_bundle, target).await
+            }
+            "revoke-role" | "revoke-user" => {
+                let target = Target::from_bundle(data_bundle, &subcommand.name)?;
+                revoke(store.as_ref(), guild_id, target).await
+            }
+            "list" => list(store.as_ref(), guild_id).await,
+            _ => Err(InteractionError::UnresolvedData(
+                "Permissions".to_string(),
+                format!("Unknown subcommand `{}`", subcommand.name),
+            )),
+        }
+    }
+}
+
+/// The role or user a subcommand targets
+enum Target {
+    Role(String),
+    User(String),
+}
+
+impl Target {
+    fn from_bundle(
+        data_bundle: &CommandDataBundle,
+        subcommand: &str,
+    ) -> Result<Self, InteractionError> {
+        if subcommand.ends_with("-role") {
+            let role = data_bundle.option::<Role>("target")?;
+            Ok(Target::Role(role.id.to_string()))
+        } else {
+            let user = data_bundle.option::<User>("target")?;
+            Ok(Target::User(user.id.to_string()))
+        }
+    }
+
+    fn mention(&self) -> String {
+        match self {
+            Target::Role(id) => format!("<@&{}>", id),
+            Target::User(id) => format!("<@{}>", id),
+        }
+    }
+}
+
+async fn grant(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    data_bundle: &CommandDataBundle,
+    target: Target,
+) -> Result<String, InteractionError> {
+    let level = PermissionLevel::from_name(&data_bundle.option::<String>("level")?);
+    let level = match level {
+        Some(level) if PermissionLevel::GRANTABLE.contains(&level) => level,
+        _ => {
+            return Err(InteractionError::Rejected(
+                "Please provide a valid permission level".to_string(),
+            ))
+        }
+    };
+
+    match &target {
+        Target::Role(id) => {
+            store
+                .set_role_permission(guild_id, id.clone(), level.name().to_string())
+                .await?
+        }
+        Target::User(id) => {
+            store
+                .set_user_permission(guild_id, id.clone(), level.name().to_string())
+                .await?
+        }
+    };
+
+    Ok(format!(
+        "{} now has the {} permission level",
+        target.mention(),
+        level.label()
+    ))
+}
+
+async fn revoke(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    target: Target,
+) -> Result<String, InteractionError> {
+    match &target {
+        Target::Role(id) => store.remove_role_permission(guild_id, id.clone()).await?,
+        Target::User(id) => store.remove_user_permission(guild_id, id.clone()).await?,
+    };
+
+    Ok(format!(
+        "{} no longer has a granted permission level",
+        target.mention()
+    ))
+}
+
+async fn list(store: &dyn BotStore, guild_id: GuildId) -> Result<String, InteractionError> {
+    let mut grants: Vec<(PermissionLevel, String)> = Vec::new();
+
+    for (id, name) in store.list_role_permissions(guild_id).await? {
+        if let Some(level) = PermissionLevel::from_name(&name) {
+            grants.push((level, Target::Role(id).mention()));
+        }
+    }
+    for (id, name) in store.list_user_permissions(guild_id).await? {
+        if let Some(level) = PermissionLevel::from_name(&name) {
+            grants.push((level, Target::User(id).mention()));
+        }
+    }
+
+    if grants.is_empty() {
+        return Ok("No permission levels have been granted".to_string());
+    }
+
+    // Highest level first
+    grants.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut content = "".to_string();
+    for (level, mention) in grants {
+        content.push_str(&format!("{}: {}\n", level.label(), mention));
+    }
+
+    Ok(content)
+}
+
+fn grant_option(name: &str, kind: CommandOptionType) -> CreateApplicationCommandOption {
+    let mut option = CreateApplicationCommandOption::default();
+    option
+        .name(name)
+        .description("Grant a permission level")
+        .kind(CommandOptionType::SubCommand)
+        .create_sub_option(|target| {
+            target
+                .name("target")
+                .description("Who to grant the level to")
+                .kind(kind)
+                .required(true)
+        })
+        .create_sub_option(|level_option| {
+            level_option
+                .name("level")
+                .description("The permission level")
+                .kind(CommandOptionType::String)
+                .required(true);
+            for level in PermissionLevel::GRANTABLE {
+                level_option.add_string_choice(level.label(), level.name());
+            }
+            level_option
+        });
+    option
+}
+
+fn revoke_option(name: &str, kind: CommandOptionType) -> CreateApplicationCommandOption {
+    let mut option = CreateApplicationCommandOption::default();
+    option
+        .name(name)
+        .description("Remove a granted permission level")
+        .kind(CommandOptionType::SubCommand)
+        .create_sub_option(|target| {
+            target
+                .name("target")
+                .description("Who to remove the level from")
+                .kind(kind)
+                .required(true)
+        });
+    option
+}
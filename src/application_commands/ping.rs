@@ -1,16 +1,31 @@
 use crate::{
-    application_commands::errors::CommandError, events::application_command::CommandDataBundle,
+    application_commands::SlashCommand, events::application_command::CommandDataBundle,
+    interaction_error::InteractionError, permissions::PermissionLevel,
 };
-use serenity::builder::CreateApplicationCommand;
+use async_trait::async_trait;
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct Ping;
 
-    Ok("Hey, I'm alive!".to_string())
-}
+#[async_trait]
+impl SlashCommand for Ping {
+    fn name(&self) -> &'static str {
+        "ping"
+    }
+
+    fn description(&self) -> &'static str {
+        "Check if bot is online"
+    }
+
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Everyone
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command.name("ping").description("Check if bot is online")
+        Ok("Hey, I'm alive!".to_string())
     }
 }
@@ -1,77 +1,114 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::permissions::PermissionLevel;
+use async_trait::async_trait;
 use serenity::builder::CreateApplicationCommand;
+use serenity::client::Context;
 use serenity::model::application::command::CommandOptionType;
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+use serenity::model::id::{ChannelId, UserId};
+use serenity::prelude::TypeMapKey;
 use serenity::{futures::StreamExt, model::id::MessageId};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
-
-    let channel_id = data_bundle.interaction.channel_id;
-    let http = data_bundle.ctx.http.to_owned();
-    let command_interaction = &data_bundle.interaction;
-    let u_amount: usize;
-
-    let amount = command_interaction.data.options.get(0);
-    let amount = match amount {
-        Some(a) => a,
-        None => return Err(CommandError::ArgumentMissing("Prune".to_string())),
-    };
-
-    let amount = amount.resolved.as_ref();
-    let amount = match amount {
-        Some(a) => a,
-        None => return Err(CommandError::ArgumentMissing("Prune".to_string())),
-    };
-
-    if let CommandDataOptionValue::Integer(set_amount) = amount {
-        if *set_amount < 0 {
-            u_amount = 0;
-        } else {
-            u_amount = *set_amount as usize;
-        }
-    } else {
-        return Ok("Please provide a valid amount".to_string());
+// The delete event arrives right after the bulk delete, anything older is someone else's
+const PRUNE_ATTRIBUTION_WINDOW: Duration = Duration::from_secs(30);
+
+pub struct Prune;
+
+/// Who last ran `/prune` in each channel, so the delete logs can credit them
+pub struct RecentPrunes;
+
+impl TypeMapKey for RecentPrunes {
+    type Value = HashMap<ChannelId, (UserId, Instant)>;
+}
+
+async fn record_prune(ctx: &Context, channel_id: ChannelId, user_id: UserId) {
+    let mut data = ctx.data.write().await;
+    data.entry::<RecentPrunes>()
+        .or_insert_with(HashMap::new)
+        .insert(channel_id, (user_id, Instant::now()));
+}
+
+/// The member whose `/prune` just deleted messages in `channel_id`, if any
+pub async fn take_prune(ctx: &Context, channel_id: ChannelId) -> Option<UserId> {
+    let mut data = ctx.data.write().await;
+    let (user_id, pruned_at) = data.get_mut::<RecentPrunes>()?.remove(&channel_id)?;
+
+    match pruned_at.elapsed() < PRUNE_ATTRIBUTION_WINDOW {
+        true => Some(user_id),
+        false => None,
+    }
+}
+
+#[async_trait]
+impl SlashCommand for Prune {
+    fn name(&self) -> &'static str {
+        "prune"
+    }
+
+    fn description(&self) -> &'static str {
+        "Delete 'x' amount of messages"
     }
 
-    let mut messages = channel_id.messages_iter(&http).boxed();
-    let mut message_ids: Vec<MessageId> = Vec::new();
-    while let Some(message_result) = messages.next().await {
-        match message_result {
-            Ok(message) => {
-                if message_ids.len() < u_amount {
-                    message_ids.push(message.id)
-                } else {
-                    break;
-                };
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Moderator
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("amount")
+                .description("Amount to delete")
+                .kind(CommandOptionType::Integer)
+                .min_int_value(1)
+                .max_int_value(100)
+                .required(true)
+        });
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let channel_id = data_bundle.interaction.channel_id;
+        let http = data_bundle.ctx.http.to_owned();
+        // Discord bulk deletes at most 100 messages at a time
+        let amount = data_bundle.integer_option("amount", 1..=100)? as usize;
+
+        let mut messages = channel_id.messages_iter(&http).boxed();
+        let mut message_ids: Vec<MessageId> = Vec::new();
+        while let Some(message_result) = messages.next().await {
+            match message_result {
+                Ok(message) => {
+                    if message_ids.len() < amount {
+                        message_ids.push(message.id)
+                    } else {
+                        break;
+                    };
+                }
+                Err(error) => return Err(InteractionError::Other(error.to_string())),
             }
-            Err(error) => return Err(CommandError::Other(error.to_string())),
         }
-    }
 
-    match channel_id
-        .delete_messages(&http, message_ids.into_iter())
-        .await
-    {
-        Ok(x) => x,
-        Err(e) => return Err(CommandError::Other(e.to_string())),
-    };
+        record_prune(
+            &data_bundle.ctx,
+            channel_id,
+            data_bundle.interaction.user.id,
+        )
+        .await;
 
-    Ok("Prune done!".to_string())
-}
+        match channel_id
+            .delete_messages(&http, message_ids.into_iter())
+            .await
+        {
+            Ok(x) => x,
+            Err(e) => return Err(InteractionError::Other(e.to_string())),
+        };
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("prune")
-            .description("Delete 'x' amount of messages")
-            .create_option(|option| {
-                option
-                    .name("amount")
-                    .description("Amount to delete")
-                    .kind(CommandOptionType::Integer)
-                    .required(true)
-            })
+        Ok("Prune done!".to_string())
     }
 }
@@ -1,65 +1,57 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
-use crate::redis_client::{self, get_master_admin, remove_admin};
+use crate::interaction_error::InteractionError;
+use crate::permissions::is_owner;
+use crate::store::get_store;
+use async_trait::async_trait;
 use serenity::builder::CreateApplicationCommand;
 use serenity::model::application::command::CommandOptionType;
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
+use serenity::model::user::User;
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct RemoveAdmin;
 
-    let command_interaction = &data_bundle.interaction;
-    let options = command_interaction.data.options.get(0);
-    let options = match options {
-        Some(x) => x,
-        None => return Err(CommandError::ArgumentMissing("Remove Admin".to_string())),
-    };
+#[async_trait]
+impl SlashCommand for RemoveAdmin {
+    fn name(&self) -> &'static str {
+        "remove-admin"
+    }
 
-    let options = options.resolved.as_ref();
-    let options = match options {
-        Some(x) => x,
-        None => {
-            return Err(CommandError::UnresolvedData(
-                "Remove Admin".to_string(),
-                "Expected user object".to_string(),
-            ))
-        }
-    };
+    fn description(&self) -> &'static str {
+        "Remove user from the admin list"
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("id")
+                .description("The user to remove")
+                .kind(CommandOptionType::User)
+                .required(true)
+        });
+    }
 
-    if let CommandDataOptionValue::User(user, _member) = options {
-        let mut connection = redis_client::connect();
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let user = data_bundle.option::<User>("id")?;
+
+        let store = get_store(&data_bundle.ctx).await?;
         let user_id = user.id.to_string();
-        let master_admin = get_master_admin();
-        if user_id == master_admin {
-            return Ok("Cannot remove master admin".to_string());
+        if is_owner(&data_bundle.ctx, store.as_ref(), guild_id, &user_id).await? {
+            return Ok("Cannot remove an owner".to_string());
         };
-        match remove_admin(&mut connection, user_id) {
+        match store.remove_admin(guild_id, user_id).await {
             Ok(_) => Ok(format!(
                 "{} has been removed from the admin list",
                 user.tag()
             )),
-            Err(_) => Err(CommandError::RedisError(
+            Err(_) => Err(InteractionError::RedisError(
                 "remove_admin() failed".to_string(),
             )),
         }
-    } else {
-        Err(CommandError::Other(
-            "Please provide a valid user".to_string(),
-        ))
-    }
-}
-
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("remove-admin")
-            .description("Remove user from the admin list")
-            .create_option(|option| {
-                option
-                    .name("id")
-                    .description("The user to remove")
-                    .kind(CommandOptionType::User)
-                    .required(true)
-            })
     }
 }
@@ -1,50 +1,59 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
-use serenity::builder::CreateApplicationCommand;
+use crate::interaction_error::InteractionError;
+use async_trait::async_trait;
 use serenity::model::application::component::ButtonStyle;
 use serenity::model::prelude::interaction::InteractionResponseType;
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct SetupPickGamesModal;
 
-    let ctx = &data_bundle.ctx;
+#[async_trait]
+impl SlashCommand for SetupPickGamesModal {
+    fn name(&self) -> &'static str {
+        "setup-pick-games-modal"
+    }
+
+    fn description(&self) -> &'static str {
+        "Send an embedded button message that triggers a 'Pick Your Games' modal"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
 
-    let success = data_bundle
-        .interaction
-        .create_interaction_response(&ctx.http, |response| {
-            response
-                .kind(InteractionResponseType::ChannelMessageWithSource)
-                .interaction_response_data(|message| {
-                    message.content("Pick Your Games").components(|c| {
-                        c.create_action_row(|row| {
-                            row.create_button(|button| {
-                                button
-                                    .custom_id("pick-games-add")
-                                    .label("Add")
-                                    .style(ButtonStyle::Success)
-                            });
-                            row.create_button(|button| {
-                                button
-                                    .custom_id("pick-games-remove")
-                                    .label("Remove")
-                                    .style(ButtonStyle::Danger)
+        let ctx = &data_bundle.ctx;
+
+        let success = data_bundle
+            .interaction
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|message| {
+                        message.content("Pick Your Games").components(|c| {
+                            c.create_action_row(|row| {
+                                row.create_button(|button| {
+                                    button
+                                        .custom_id("pick-games-add")
+                                        .label("Add")
+                                        .style(ButtonStyle::Success)
+                                });
+                                row.create_button(|button| {
+                                    button
+                                        .custom_id("pick-games-remove")
+                                        .label("Remove")
+                                        .style(ButtonStyle::Danger)
+                                })
                             })
                         })
                     })
-                })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok(String::new()),
-        Err(e) => Err(CommandError::Other(e.to_string())),
-    }
-}
+            })
+            .await;
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("setup-pick-games-modal")
-            .description("Send an embedded button message that triggers a 'Pick Your Games' modal")
+        match success {
+            Ok(_) => Ok(String::new()),
+            Err(e) => Err(InteractionError::Other(e.to_string())),
+        }
     }
 }
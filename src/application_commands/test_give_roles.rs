@@ -1,40 +1,45 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use async_trait::async_trait;
 use serenity::builder::CreateApplicationCommand;
 use serenity::model::application::command::CommandOptionType;
-use serenity::model::application::interaction::application_command::CommandDataOptionValue;
 use serenity::model::prelude::RoleId;
+use serenity::model::user::User;
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct TestGiveRoles;
 
-    let command_interaction = &data_bundle.interaction;
-    let ctx = &data_bundle.ctx;
+#[async_trait]
+impl SlashCommand for TestGiveRoles {
+    fn name(&self) -> &'static str {
+        "test-give-roles"
+    }
 
-    let options = command_interaction.data.options.get(0);
-    let options = match options {
-        Some(x) => x,
-        None => return Err(CommandError::ArgumentMissing("Get User ID".to_string())),
-    };
+    fn description(&self) -> &'static str {
+        "Give 2 test roles"
+    }
 
-    let options = options.resolved.as_ref();
-    let options = match options {
-        Some(x) => x,
-        None => {
-            return Err(CommandError::UnresolvedData(
-                "Get User ID".to_string(),
-                "Expected user object".to_string(),
-            ))
-        }
-    };
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("id")
+                .description("The user to lookup")
+                .kind(CommandOptionType::User)
+                .required(true)
+        });
+    }
 
-    if let CommandDataOptionValue::User(user, _partial_member) = options {
-        let mut member = command_interaction
-            .guild_id
-            .unwrap()
-            .member(&ctx.http, user)
-            .await
-            .unwrap();
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let user = data_bundle.option::<User>("id")?;
+        let ctx = &data_bundle.ctx;
+
+        let mut member = guild_id.member(&ctx.http, &user).await.unwrap();
 
         member
             .add_roles(
@@ -45,24 +50,5 @@ pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, Comm
             .expect("Adding roles failed");
 
         Ok(format!("{}'s has been given test roles", user.tag()))
-    } else {
-        Err(CommandError::Other(
-            "Please provide a valid user".to_string(),
-        ))
-    }
-}
-
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("test-give-roles")
-            .description("Give 2 test roles")
-            .create_option(|option| {
-                option
-                    .name("id")
-                    .description("The user to lookup")
-                    .kind(CommandOptionType::User)
-                    .required(true)
-            })
     }
 }
@@ -1,85 +1,97 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
-use crate::redis_client;
+use crate::interaction_error::InteractionError;
+use crate::store::get_store;
 use crate::utils::discord_cdn::get_avatar_url;
 use crate::utils::time::date_diff;
+use async_trait::async_trait;
 use chrono::Utc;
-use serenity::builder::{CreateApplicationCommand, CreateEmbedAuthor, CreateEmbedFooter};
+use serenity::builder::{CreateEmbedAuthor, CreateEmbedFooter};
 use serenity::model::id::{ChannelId, UserId};
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct TestLogChannel;
 
-    let ctx = &data_bundle.ctx;
-    let user_id = UserId(224597366324461568);
-    let mut conn = redis_client::connect();
+#[async_trait]
+impl SlashCommand for TestLogChannel {
+    fn name(&self) -> &'static str {
+        "test-log-channel"
+    }
+
+    fn description(&self) -> &'static str {
+        "Send an embedded message to the log channel"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
 
-    // Query and unpack the log channel id from Redis
-    let channel_id = match redis_client::get_major_log_channel(&mut conn) {
-        Ok(value) => match value {
-            Some(value) => match value.parse::<u64>() {
-                Ok(value) => value,
-                Err(_) => {
-                    return Err(CommandError::Other(
-                        "Could not parse log channel id into u64".to_string(),
+        let ctx = &data_bundle.ctx;
+        let user_id = UserId(224597366324461568);
+        let store = get_store(ctx).await?;
+
+        // Query and unpack the log channel id from Redis
+        let channel_id = match store.get_major_log_channel(guild_id).await {
+            Ok(value) => match value {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(value) => value,
+                    Err(_) => {
+                        return Err(InteractionError::Other(
+                            "Could not parse log channel id into u64".to_string(),
+                        ))
+                    }
+                },
+                None => {
+                    return Err(InteractionError::NotConfigured(
+                        "Major log channel".to_string(),
                     ))
                 }
             },
-            None => {
-                return Err(CommandError::RedisError(
-                    "Could not resolve log channel id".to_string(),
-                ))
-            }
-        },
-        Err(e) => return Err(CommandError::Other(e.to_string())),
-    };
+            Err(e) => return Err(InteractionError::Other(e.to_string())),
+        };
 
-    let channel_id = ChannelId(channel_id);
+        let channel_id = ChannelId(channel_id);
 
-    let user = match user_id.to_user(&ctx.http).await {
-        Ok(x) => x,
-        Err(e) => return Err(CommandError::Other(e.to_string())),
-    };
+        let user = match user_id.to_user(&ctx.http).await {
+            Ok(x) => x,
+            Err(e) => return Err(InteractionError::Other(e.to_string())),
+        };
 
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(&user));
-                author.name(user.name.clone());
+        let success = channel_id
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| {
+                    let mut author = CreateEmbedAuthor::default();
+                    author.icon_url(get_avatar_url(&user));
+                    author.name(user.name.clone());
 
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
+                    let mut footer = CreateEmbedFooter::default();
+                    footer.text(format!("ID: {}", user.id));
 
-                let account_age = date_diff(&user.created_at());
+                    let account_age = date_diff(&user.created_at());
 
-                e.title("Member Joined")
-                    .color(0x50C878)
-                    .description(format!(
-                        "<@{}> - {}#{}",
-                        user.id, user.name, user.discriminator
-                    ))
-                    .image(get_avatar_url(&user))
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field("Account Age", account_age, true)
-                    .field("Roles Given: ", "<@&888565264705273886>", false)
-                    .field("Roles Taken: ", "<@&888565264705273886>", false)
-                    .set_footer(footer)
+                    e.title("Member Joined")
+                        .color(0x50C878)
+                        .description(format!(
+                            "<@{}> - {}#{}",
+                            user.id, user.name, user.discriminator
+                        ))
+                        .image(get_avatar_url(&user))
+                        .timestamp(Utc::now())
+                        .set_author(author)
+                        .field("Account Age", account_age, true)
+                        .field("Roles Given: ", "<@&888565264705273886>", false)
+                        .field("Roles Taken: ", "<@&888565264705273886>", false)
+                        .set_footer(footer)
+                })
             })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok("Message sent to logs...".to_string()),
-        Err(e) => Err(CommandError::Other(e.to_string())),
-    }
-}
+            .await;
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("test-log-channel")
-            .description("Send an embedded message to the log channel")
+        match success {
+            Ok(_) => Ok("Message sent to logs...".to_string()),
+            Err(e) => Err(InteractionError::Other(e.to_string())),
+        }
     }
 }
@@ -1,57 +1,70 @@
-use crate::application_commands::errors::CommandError;
+use crate::application_commands::SlashCommand;
 use crate::events::application_command::CommandDataBundle;
-use crate::redis_client;
-use serenity::builder::CreateApplicationCommand;
+use crate::interaction_error::InteractionError;
+use crate::store::get_store;
+use async_trait::async_trait;
 use serenity::model::id::ChannelId;
 
-pub async fn execute(data_bundle: &mut CommandDataBundle) -> Result<String, CommandError> {
-    data_bundle.set_ephemeral(true);
+pub struct TestModal;
 
-    let ctx = &data_bundle.ctx;
+#[async_trait]
+impl SlashCommand for TestModal {
+    fn name(&self) -> &'static str {
+        "test-modal"
+    }
+
+    fn description(&self) -> &'static str {
+        "Send an embedded button message that triggers a modal"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
 
-    let mut conn = redis_client::connect();
+        let ctx = &data_bundle.ctx;
 
-    // Query and unpack the log channel id from Redis
-    let channel_id = match redis_client::get_major_log_channel(&mut conn) {
-        Ok(value) => match value {
-            Some(value) => match value.parse::<u64>() {
-                Ok(value) => value,
-                Err(_) => {
-                    return Err(CommandError::Other(
-                        "Could not parse log channel id into u64".to_string(),
+        let store = get_store(ctx).await?;
+
+        // Query and unpack the log channel id from Redis
+        let channel_id = match store.get_major_log_channel(guild_id).await {
+            Ok(value) => match value {
+                Some(value) => match value.parse::<u64>() {
+                    Ok(value) => value,
+                    Err(_) => {
+                        return Err(InteractionError::Other(
+                            "Could not parse log channel id into u64".to_string(),
+                        ))
+                    }
+                },
+                None => {
+                    return Err(InteractionError::NotConfigured(
+                        "Major log channel".to_string(),
                     ))
                 }
             },
-            None => {
-                return Err(CommandError::RedisError(
-                    "Could not resolve log channel id".to_string(),
-                ))
-            }
-        },
-        Err(e) => return Err(CommandError::Other(e.to_string())),
-    };
-
-    let channel_id = ChannelId(channel_id);
-    let success = channel_id
-        .send_message(&ctx, |m| {
-            m.content("Say hi to the bot").components(|c| {
-                c.create_action_row(|row| {
-                    row.create_button(|button| button.custom_id("test-modal").label("Open Modal"))
+            Err(e) => return Err(InteractionError::Other(e.to_string())),
+        };
+
+        let channel_id = ChannelId(channel_id);
+        let success = channel_id
+            .send_message(&ctx, |m| {
+                m.content("Say hi to the bot").components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|button| {
+                            button.custom_id("test-modal").label("Open Modal")
+                        })
+                    })
                 })
             })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok("Message sent to logs...".to_string()),
-        Err(e) => Err(CommandError::Other(e.to_string())),
-    }
-}
+            .await;
 
-pub fn setup() -> impl FnOnce(&mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
-    move |command: &mut CreateApplicationCommand| {
-        command
-            .name("test-modal")
-            .description("Send an embedded button message that triggers a modal")
+        match success {
+            Ok(_) => Ok("Message sent to logs...".to_string()),
+            Err(e) => Err(InteractionError::Other(e.to_string())),
+        }
     }
 }
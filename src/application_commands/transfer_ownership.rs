@@ -0,0 +1,72 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::permissions::{configured_owner_ids, PermissionLevel};
+use crate::store::get_store;
+use async_trait::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::user::User;
+
+pub struct TransferOwnership;
+
+#[async_trait]
+impl SlashCommand for TransferOwnership {
+    fn name(&self) -> &'static str {
+        "transfer-ownership"
+    }
+
+    fn description(&self) -> &'static str {
+        "Hand ownership of the bot in this server to another user"
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("id")
+                .description("The new owner")
+                .kind(CommandOptionType::User)
+                .required(true)
+        });
+    }
+
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Owner
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+
+        let user = data_bundle.option::<User>("id")?;
+
+        if user.bot {
+            return Err(InteractionError::Rejected(
+                "A bot can't own the bot".to_string(),
+            ));
+        }
+
+        let store = get_store(&data_bundle.ctx).await?;
+        store.set_owner(guild_id, user.id.to_string()).await?;
+
+        let mut content = format!("{} now owns the bot in this server", user.tag());
+
+        // `OWNER_IDS` is config, a transfer can't take ownership away from them
+        let caller_id = data_bundle
+            .interaction
+            .member
+            .as_ref()
+            .map(|member| member.user.id.to_string());
+        if let Some(caller_id) = caller_id {
+            if configured_owner_ids().contains(&caller_id) {
+                content.push_str(", you remain an owner through `OWNER_IDS`");
+            }
+        }
+
+        Ok(content)
+    }
+}
@@ -1,24 +1,30 @@
+use serenity::model::id::GuildId;
 use serenity::model::prelude::command::Command;
 use serenity::Error;
 use std::env;
+use tracing::info;
 
-pub fn check_command_reg_verbose(
+fn verbose_command_registration() -> String {
+    env::var("VERBOSE_COMMAND_REG").unwrap_or_else(|_| "no_verbose".to_string())
+}
+
+pub fn check_guild_command_reg_verbose(
+    guild_id: &GuildId,
     guild_commands: Result<Vec<Command>, Error>,
-    global_command: Result<Command, Error>,
 ) {
-    let verbose_command_registration =
-        env::var("VERBOSE_COMMAND_REG").unwrap_or_else(|_| "no_verbose".to_string());
-
-    if verbose_command_registration == *"guild" {
-        println!(
-            "I now have the following guild slash commands: {:#?}",
-            guild_commands
+    if verbose_command_registration() == *"guild" {
+        info!(
+            "I now have the following guild slash commands in {}: {:#?}",
+            guild_id, guild_commands
         );
     }
-    if verbose_command_registration == *"global" {
-        println!(
+}
+
+pub fn check_global_command_reg_verbose(global_commands: Result<Vec<Command>, Error>) {
+    if verbose_command_registration() == *"global" {
+        info!(
             "I now have the following global slash commands: {:#?}",
-            global_command
+            global_commands
         );
     }
 }
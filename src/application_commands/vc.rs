@@ -0,0 +1,234 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::permissions::PermissionLevel;
+use crate::store::get_store;
+use crate::temp_voice;
+use async_trait::async_trait;
+use chrono::Utc;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::id::ChannelId;
+use serenity::model::user::User;
+
+pub struct Vc;
+
+#[async_trait]
+impl SlashCommand for Vc {
+    fn name(&self) -> &'static str {
+        "vc"
+    }
+
+    fn description(&self) -> &'static str {
+        "Manage the temporary voice channel you own"
+    }
+
+    // Anyone can own a temporary channel, ownership is checked when running
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Everyone
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command
+            .create_option(|option| {
+                option
+                    .name("rename")
+                    .description("Rename your channel")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|name_option| {
+                        name_option
+                            .name("name")
+                            .description("The new name")
+                            .kind(CommandOptionType::String)
+                            .required(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("limit")
+                    .description("Limit how many members can join")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|users_option| {
+                        users_option
+                            .name("users")
+                            .description("Most members at once, 0 for no limit")
+                            .kind(CommandOptionType::Integer)
+                            .min_int_value(0)
+                            .max_int_value(99)
+                            .required(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("lock")
+                    .description("Stop anyone else joining")
+                    .kind(CommandOptionType::SubCommand)
+            })
+            .create_option(|option| {
+                option
+                    .name("unlock")
+                    .description("Let anyone join again")
+                    .kind(CommandOptionType::SubCommand)
+            })
+            .create_option(|option| {
+                option
+                    .name("kick")
+                    .description("Disconnect a member and keep them out")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|user_option| {
+                        user_option
+                            .name("user")
+                            .description("The member to kick")
+                            .kind(CommandOptionType::User)
+                            .required(true)
+                    })
+            })
+            .create_option(|option| {
+                option
+                    .name("transfer")
+                    .description("Hand your channel to another member in it")
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|user_option| {
+                        user_option
+                            .name("user")
+                            .description("The new owner")
+                            .kind(CommandOptionType::User)
+                            .required(true)
+                    })
+            });
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let ctx = &data_bundle.ctx;
+        let user_id = data_bundle.interaction.user.id;
+        let store = get_store(ctx).await?;
+
+        let subcommand = match data_bundle.interaction.data.options.get(0) {
+            Some(x) => x.name.clone(),
+            None => return Err(InteractionError::ArgumentMissing("Vc".to_string())),
+        };
+
+        // Only the owner of the channel they are in can manage it
+        let channel_id = match temp_voice::current_channel(ctx, guild_id, user_id) {
+            Some(x) => x,
+            None => {
+                return Err(InteractionError::Rejected(
+                    "Join your temporary voice channel first".to_string(),
+                ))
+            }
+        };
+        match temp_voice::temp_channels(store.as_ref(), guild_id)
+            .await?
+            .get(&channel_id)
+        {
+            Some(owner_id) if *owner_id == user_id => {}
+            Some(owner_id) => {
+                return Err(InteractionError::Rejected(format!(
+                    "Only <@{}> can manage <#{}>",
+                    owner_id, channel_id
+                )))
+            }
+            None => {
+                return Err(InteractionError::Rejected(format!(
+                    "<#{}> is not a temporary voice channel",
+                    channel_id
+                )))
+            }
+        }
+
+        let http = &ctx.http;
+        let done = match subcommand.as_str() {
+            "rename" => {
+                let name = data_bundle.option::<String>("name")?;
+                if name.trim().is_empty() || name.chars().count() > 100 {
+                    return Err(InteractionError::Rejected(
+                        "Channel names are 1 to 100 characters long".to_string(),
+                    ));
+                }
+                // Discord would hold the rename back well past the interaction timeout
+                if let Err(wait) = temp_voice::claim_rename(channel_id) {
+                    return Err(InteractionError::Rejected(format!(
+                        "Discord only allows {} renames every 10 minutes, try again <t:{}:R>",
+                        temp_voice::RENAMES_PER_WINDOW,
+                        Utc::now().timestamp() + wait.as_secs() as i64 + 1
+                    )));
+                }
+                channel_id
+                    .edit(http, |c| c.name(&name))
+                    .await
+                    .map(|_| format!("Renamed <#{}>", channel_id))
+            }
+            "limit" => {
+                let users = data_bundle.integer_option("users", 0..=99)?;
+                channel_id
+                    .edit(http, |c| c.user_limit(users as u64))
+                    .await
+                    .map(|_| match users {
+                        0 => format!("<#{}> has no member limit", channel_id),
+                        users => format!("<#{}> is limited to {} members", channel_id, users),
+                    })
+            }
+            "lock" => temp_voice::set_locked(ctx, guild_id, channel_id, user_id, true)
+                .await
+                .map(|_| format!("<#{}> is locked", channel_id)),
+            "unlock" => temp_voice::set_locked(ctx, guild_id, channel_id, user_id, false)
+                .await
+                .map(|_| format!("<#{}> is open to everyone again", channel_id)),
+            "kick" => {
+                let user = in_channel(data_bundle, channel_id)?;
+                if user.id == user_id {
+                    return Err(InteractionError::Rejected(
+                        "Leave the channel to disconnect yourself".to_string(),
+                    ));
+                }
+                temp_voice::kick(ctx, guild_id, channel_id, user.id)
+                    .await
+                    .map(|_| format!("Kicked <@{}> from <#{}>", user.id, channel_id))
+            }
+            "transfer" => {
+                let user = in_channel(data_bundle, channel_id)?;
+                if user.bot {
+                    return Err(InteractionError::Rejected(
+                        "Bots can't own a channel".to_string(),
+                    ));
+                }
+                temp_voice::transfer(ctx, store.as_ref(), guild_id, channel_id, user_id, user.id)
+                    .await
+                    .map_err(|error| InteractionError::Other(error.to_string()))?;
+                return Ok(format!("<@{}> now owns <#{}>", user.id, channel_id));
+            }
+            _ => {
+                return Err(InteractionError::UnresolvedData(
+                    "Vc".to_string(),
+                    format!("Unknown subcommand `{}`", subcommand),
+                ))
+            }
+        };
+
+        done.map_err(|error| InteractionError::Other(error.to_string()))
+    }
+}
+
+// The `user` option, who has to be in `channel_id`
+fn in_channel(
+    data_bundle: &CommandDataBundle,
+    channel_id: ChannelId,
+) -> Result<User, InteractionError> {
+    let user = data_bundle.option::<User>("user")?;
+    let guild_id = data_bundle.guild_id()?;
+
+    if temp_voice::current_channel(&data_bundle.ctx, guild_id, user.id) != Some(channel_id) {
+        return Err(InteractionError::Rejected(format!(
+            "<@{}> is not in <#{}>",
+            user.id, channel_id
+        )));
+    }
+
+    Ok(user)
+}
@@ -0,0 +1,105 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::log_channel::entry::truncate;
+use crate::permissions::PermissionLevel;
+use crate::store::get_store;
+use crate::voice_sessions::{self, format_duration, VoiceSession, VoiceTotals};
+use async_trait::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::user::User;
+
+// Sessions listed in the reply, the totals still count every stored session
+const SHOWN_SESSIONS: usize = 10;
+
+pub struct VoiceHistory;
+
+#[async_trait]
+impl SlashCommand for VoiceHistory {
+    fn name(&self) -> &'static str {
+        "voice-history"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show when and how long a member was in voice"
+    }
+
+    fn permission(&self) -> PermissionLevel {
+        PermissionLevel::Moderator
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        command.create_option(|option| {
+            option
+                .name("user")
+                .description("The member to look up")
+                .kind(CommandOptionType::User)
+                .required(true)
+        });
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let user = data_bundle.option::<User>("user")?;
+        let store = get_store(&data_bundle.ctx).await?;
+
+        let open = voice_sessions::open_session(store.as_ref(), guild_id, user.id).await?;
+        let history = voice_sessions::history(store.as_ref(), guild_id, user.id).await?;
+
+        if open.is_none() && history.is_empty() {
+            return Ok(format!(
+                "{} has not been in voice in the last {} days",
+                user.tag(),
+                voice_sessions::RETENTION_DAYS
+            ));
+        }
+
+        let totals = VoiceTotals::new(open.iter().chain(history.iter()));
+        let mut content = format!(
+            "**{}** spent {} in voice in the last 7 days and {} in the last 30 days\n",
+            user.tag(),
+            format_duration(totals.week),
+            format_duration(totals.month)
+        );
+
+        if let Some(session) = &open {
+            content.push_str(&format!("\nIn voice now: {}\n", session_line(session)));
+        }
+
+        content.push_str("\nRecent sessions:\n");
+        for session in history.iter().take(SHOWN_SESSIONS) {
+            content.push_str(&session_line(session));
+            content.push('\n');
+        }
+
+        // Message content is capped at 2000 characters
+        Ok(truncate(&content, 1900))
+    }
+}
+
+// e.g. "<t:1700000000:f> for 2h13m in #Lobby → #Raid"
+fn session_line(session: &VoiceSession) -> String {
+    let channels: Vec<String> = session
+        .channels
+        .iter()
+        .map(|channel_id| format!("<#{}>", channel_id))
+        .collect();
+
+    let mut line = format!(
+        "<t:{}:f> for {} in {}",
+        session.started_at,
+        format_duration(session.duration()),
+        channels.join(" → ")
+    );
+    if session.interrupted {
+        line.push_str(" (left while the bot was offline)");
+    }
+
+    line
+}
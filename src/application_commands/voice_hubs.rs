@@ -0,0 +1,117 @@
+use crate::application_commands::SlashCommand;
+use crate::events::application_command::CommandDataBundle;
+use crate::interaction_error::InteractionError;
+use crate::store::get_store;
+use crate::temp_voice;
+use async_trait::async_trait;
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::application::command::CommandOptionType;
+use serenity::model::channel::ChannelType;
+
+pub struct VoiceHubs;
+
+#[async_trait]
+impl SlashCommand for VoiceHubs {
+    fn name(&self) -> &'static str {
+        "voice-hubs"
+    }
+
+    fn description(&self) -> &'static str {
+        "Voice channels that create a temporary channel for whoever joins them"
+    }
+
+    fn options(&self, command: &mut CreateApplicationCommand) {
+        for (name, description) in [
+            (
+                "add",
+                "Create a temporary channel for whoever joins this channel",
+            ),
+            (
+                "remove",
+                "Stop creating temporary channels from this channel",
+            ),
+        ] {
+            command.create_option(|option| {
+                option
+                    .name(name)
+                    .description(description)
+                    .kind(CommandOptionType::SubCommand)
+                    .create_sub_option(|channel_option| {
+                        channel_option
+                            .name("channel")
+                            .description("The hub voice channel")
+                            .kind(CommandOptionType::Channel)
+                            .channel_types(&[ChannelType::Voice])
+                            .required(true)
+                    })
+            });
+        }
+
+        command.create_option(|option| {
+            option
+                .name("list")
+                .description("Show every hub channel")
+                .kind(CommandOptionType::SubCommand)
+        });
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut CommandDataBundle,
+    ) -> Result<String, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let guild_id = data_bundle.guild_id()?;
+        let store = get_store(&data_bundle.ctx).await?;
+
+        let subcommand = match data_bundle.interaction.data.options.get(0) {
+            Some(x) => x,
+            None => return Err(InteractionError::ArgumentMissing("Voice Hubs".to_string())),
+        };
+
+        match subcommand.name.as_str() {
+            "add" => {
+                let channel = data_bundle.channel_option("channel", &[ChannelType::Voice])?;
+
+                // Every join would create yet another temporary channel
+                let temp_channels = temp_voice::temp_channels(store.as_ref(), guild_id).await?;
+                if temp_channels.contains_key(&channel.id) {
+                    return Err(InteractionError::Rejected(format!(
+                        "<#{}> is a temporary channel and can't be a hub",
+                        channel.id
+                    )));
+                }
+
+                store
+                    .add_voice_hub(guild_id, channel.id.to_string())
+                    .await?;
+                Ok(format!(
+                    "Joining <#{}> now creates a temporary voice channel",
+                    channel.id
+                ))
+            }
+            "remove" => {
+                let channel = data_bundle.channel_option("channel", &[ChannelType::Voice])?;
+                store
+                    .remove_voice_hub(guild_id, channel.id.to_string())
+                    .await?;
+                Ok(format!("<#{}> is no longer a hub", channel.id))
+            }
+            "list" => {
+                let hubs = store.list_voice_hubs(guild_id).await?;
+                if hubs.is_empty() {
+                    return Ok(
+                        "There are no hub channels, add one with `/voice-hubs add`".to_string()
+                    );
+                }
+
+                let hubs: Vec<String> = hubs.iter().map(|id| format!("<#{}>", id)).collect();
+                Ok(format!("Hub channels: {}", hubs.join(" ")))
+            }
+            _ => Err(InteractionError::UnresolvedData(
+                "Voice Hubs".to_string(),
+                format!("Unknown subcommand `{}`", subcommand.name),
+            )),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::Utc;
+use serde_json::json;
+use serenity::client::Context;
+use serenity::http::Http;
+use serenity::model::guild::audit_log::{Action, MemberAction};
+use serenity::model::guild::Member;
+use serenity::model::id::{GuildId, RoleId, UserId};
+use std::time::Duration;
+use tracing::debug;
+
+// Entries older than this belong to an earlier event for the same member
+const MAX_ENTRY_AGE_SECS: i64 = 15;
+// The audit log entry is sometimes written just after the gateway event arrives
+const RETRY_DELAY: Duration = Duration::from_millis(1500);
+const ENTRY_LIMIT: u8 = 25;
+
+/// Audit log reasons for the role changes the bot makes on its own
+pub const REASON_GAME_PICKED: &str = "Picked in the game role menu";
+pub const REASON_GAME_REMOVED: &str = "Removed in the game role menu";
+pub const REASON_FOLLOWER_ROLE: &str = "Follower role given on join";
+
+/// Who made a change to a member and why, read from the guild audit log
+pub struct Attribution {
+    pub action: MemberAction,
+    pub moderator: UserId,
+    pub reason: Option<String>,
+    /// The change was made by this bot, e.g. a game role picked from the menu
+    pub by_bot: bool,
+}
+
+impl Attribution {
+    pub fn is(&self, action: MemberAction) -> bool {
+        action as u8 == self.action as u8
+    }
+}
+
+/// The most recent audit log entry for one of `actions` against `target`.
+///
+/// `None` when nothing matched or the bot can't read the audit log,
+/// which needs the View Audit Log permission. Use this for events that often have
+/// no entry at all, like a member leaving on their own
+pub async fn find_member_action(
+    ctx: &Context,
+    guild_id: GuildId,
+    target: UserId,
+    actions: &[MemberAction],
+) -> Option<Attribution> {
+    lookup(ctx, guild_id, target, actions).await
+}
+
+/// Like `find_member_action`, but looks again after a moment when nothing matched.
+///
+/// Only for events that always have an entry, like a ban, so it was just not written yet
+pub async fn wait_for_member_action(
+    ctx: &Context,
+    guild_id: GuildId,
+    target: UserId,
+    actions: &[MemberAction],
+) -> Option<Attribution> {
+    if let Some(attribution) = lookup(ctx, guild_id, target, actions).await {
+        return Some(attribution);
+    }
+
+    tokio::time::sleep(RETRY_DELAY).await;
+    lookup(ctx, guild_id, target, actions).await
+}
+
+async fn lookup(
+    ctx: &Context,
+    guild_id: GuildId,
+    target: UserId,
+    actions: &[MemberAction],
+) -> Option<Attribution> {
+    // Filtering by a single action type is done by Discord, several are filtered here
+    let action_type = match actions {
+        [action] => Some(*action as u8),
+        _ => None,
+    };
+
+    let audit_logs = match guild_id
+        .audit_logs(&ctx.http, action_type, None, None, Some(ENTRY_LIMIT))
+        .await
+    {
+        Ok(audit_logs) => audit_logs,
+        Err(error) => {
+            debug!("Could not read the audit log of {}: {}", guild_id, error);
+            return None;
+        }
+    };
+
+    let now = Utc::now().timestamp();
+
+    audit_logs
+        .entries
+        .into_iter()
+        .filter(|entry| entry.target_id == Some(target.0))
+        .filter(|entry| now - entry.id.created_at().unix_timestamp() <= MAX_ENTRY_AGE_SECS)
+        .find_map(|entry| {
+            let action = match entry.action {
+                Action::Member(action) => action,
+                _ => return None,
+            };
+            if !actions.iter().any(|wanted| *wanted as u8 == action as u8) {
+                return None;
+            }
+
+            Some(Attribution {
+                action,
+                moderator: entry.user_id,
+                reason: entry.reason,
+                by_bot: entry.user_id == ctx.cache.current_user_id(),
+            })
+        })
+}
+
+/// Replace the roles of `member`, recording `reason` in the audit log
+pub async fn set_roles(
+    http: impl AsRef<Http>,
+    member: &mut Member,
+    roles: Vec<RoleId>,
+    reason: &str,
+) -> serenity::Result<()> {
+    let mut map = serde_json::Map::new();
+    map.insert(
+        "roles".to_string(),
+        json!(roles
+            .iter()
+            .map(|role| role.0.to_string())
+            .collect::<Vec<String>>()),
+    );
+
+    let edited = http
+        .as_ref()
+        .edit_member(member.guild_id.0, member.user.id.0, &map, Some(reason))
+        .await?;
+    member.roles = edited.roles;
+
+    Ok(())
+}
@@ -1,11 +1,15 @@
 pub mod application_command;
+pub mod autocomplete;
 pub mod errors;
 pub mod guild_ban_addition;
 pub mod guild_ban_removal;
+pub mod guild_create;
 pub mod guild_member_addition;
 pub mod guild_member_removal;
 pub mod guild_member_update;
 pub mod message_component;
+pub mod message_delete;
+pub mod message_update;
 pub mod modal_submit;
 pub mod start_up;
 pub mod voice_state_update;
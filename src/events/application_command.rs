@@ -1,81 +1,155 @@
-use crate::application_commands::errors::CommandError;
-use crate::application_commands::execute_command;
-use crate::redis_client::{self, check_admin};
-use crate::utils::logging::log_error;
-use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
+use crate::application_commands::find_command;
+use crate::interaction_error::{report_error, ErrorContext, InteractionError};
+use crate::log_channel::log_permission_denied;
+use crate::log_error;
+use crate::metrics::{record_discord_http_error, record_interaction};
+use crate::permissions::{resolve_level, PermissionLevel};
+use crate::store::get_store;
+use serenity::model::application::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption,
+};
 use serenity::model::application::interaction::InteractionResponseType;
+use serenity::model::id::GuildId;
 use serenity::prelude::*;
+use std::time::Instant;
+use tracing::{info, instrument, warn};
 
-pub async fn handle(ctx: Context, application_command_interaction: ApplicationCommandInteraction) {
-    let mut data_bundle = CommandDataBundle::new(ctx, application_command_interaction);
-    let command_caller = match data_bundle.interaction.member.as_ref() {
-        Some(member) => member.user.id,
+#[instrument(
+    name = "command",
+    skip_all,
+    fields(
+        command = %interaction.data.name,
+        user_id = interaction.user.id.0,
+        guild_id = interaction.guild_id.map(|id| id.0)
+    )
+)]
+pub async fn handle(ctx: Context, interaction: ApplicationCommandInteraction) {
+    let started = Instant::now();
+    let name = interaction.data.name.clone();
+    let outcome = respond(ctx, interaction).await;
+    record_interaction("command", &name, outcome, started.elapsed());
+
+    info!(
+        outcome,
+        latency_ms = started.elapsed().as_millis() as u64,
+        "Command handled"
+    );
+}
+
+/// Run the command and answer it, returning the outcome for metrics
+async fn respond(ctx: Context, interaction: ApplicationCommandInteraction) -> &'static str {
+    let mut data_bundle = CommandDataBundle::new(ctx, interaction);
+    let command_caller = match data_bundle.interaction.member.clone() {
+        Some(member) => member,
         None => {
-            log_error(&CommandError::UnresolvedData(
-                "application_command root handle".to_string(),
+            let error = InteractionError::UnresolvedData(
+                data_bundle.interaction.data.name.clone(),
                 "Could not resolve command caller".to_string(),
-            ));
+            );
+            return respond_with_error(data_bundle, error).await;
+        }
+    };
+
+    let guild_id = match data_bundle.guild_id() {
+        Ok(guild_id) => guild_id,
+        Err(error) => {
+            log_error!(&error);
             create_response(
                 data_bundle.ctx,
                 data_bundle.interaction,
-                "Could not resolve command caller".to_string(),
+                "Commands can only be used inside a server".to_string(),
                 data_bundle.is_ephemeral,
             )
             .await;
-            return;
+            return error.kind();
         }
     };
 
-    let mut connection = redis_client::connect();
-
-    match check_admin(&mut connection, command_caller.to_string()) {
-        Ok(is_admin) => {
-            if !is_admin {
-                create_response(
-                    data_bundle.ctx,
-                    data_bundle.interaction,
-                    "You are not an admin".to_string(),
-                    data_bundle.is_ephemeral,
-                )
-                .await;
-                return;
-            }
-        }
+    let store = match get_store(&data_bundle.ctx).await {
+        Ok(store) => store,
         Err(error) => {
-            log_error(&error);
+            return respond_with_error(data_bundle, error.into()).await;
+        }
+    };
+
+    let command = match find_command(&data_bundle.interaction.data.name) {
+        Some(command) => command,
+        None => {
+            log_error!(&InteractionError::UnresolvedData(
+                data_bundle.interaction.data.name.clone(),
+                "No handler registered for this command".to_string(),
+            ));
             create_response(
                 data_bundle.ctx,
                 data_bundle.interaction,
-                "check_admin() failed".to_string(),
+                "Command removed or not implemented".to_string(),
                 data_bundle.is_ephemeral,
             )
             .await;
-            return;
+            return "unknown_command";
         }
-    }
+    };
+
+    let required_level = command.permission();
+    if required_level > PermissionLevel::Everyone {
+        let caller_level = match resolve_level(
+            &data_bundle.ctx,
+            store.as_ref(),
+            guild_id,
+            &command_caller,
+        )
+        .await
+        {
+            Ok(level) => level,
+            Err(error) => {
+                return respond_with_error(data_bundle, error.into()).await;
+            }
+        };
 
-    let content = execute_command(&mut data_bundle).await;
+        if caller_level < required_level {
+            let logged = log_permission_denied(
+                &command_caller.user,
+                command.name(),
+                required_level,
+                caller_level,
+                guild_id,
+                &data_bundle.ctx,
+            )
+            .await;
+            if let Err(error) = logged {
+                log_error!(&error);
+            }
 
-    if let Ok(content) = content {
-        if !content.is_empty() {
             create_response(
                 data_bundle.ctx,
                 data_bundle.interaction,
-                content,
+                format!(
+                    "You need the {} permission level to use this command",
+                    required_level.label()
+                ),
                 data_bundle.is_ephemeral,
             )
             .await;
+            return "denied";
         }
-    } else if let Err(error) = content {
-        log_error(&error);
-        let content = match_error(error);
-        create_response(
-            data_bundle.ctx,
-            data_bundle.interaction,
-            content,
-            data_bundle.is_ephemeral,
-        )
-        .await;
+    }
+
+    let content = command.execute(&mut data_bundle).await;
+
+    match content {
+        Ok(content) => {
+            if !content.is_empty() {
+                create_response(
+                    data_bundle.ctx,
+                    data_bundle.interaction,
+                    content,
+                    data_bundle.is_ephemeral,
+                )
+                .await;
+            }
+            "ok"
+        }
+        Err(error) => respond_with_error(data_bundle, error).await,
     }
 }
 
@@ -95,18 +169,25 @@ async fn create_response(
         })
         .await
     {
-        println!("Cannot respond to slash command: {}", why);
+        record_discord_http_error("interaction_response");
+        warn!("Cannot respond to slash command: {}", why);
     }
 }
 
-fn match_error(error: CommandError) -> String {
-    match error {
-        CommandError::ArgumentMissing(_) => "Missing an option...".to_string(),
-        CommandError::RedisError(content) => content,
-        CommandError::Other(content) => content,
-        CommandError::CacheError(content) => content,
-        CommandError::UnresolvedData(_, content) => content,
-    }
+async fn respond_with_error(
+    data_bundle: CommandDataBundle,
+    error: InteractionError,
+) -> &'static str {
+    let context = data_bundle.error_context();
+    let content = report_error(&data_bundle.ctx, &error, &context).await;
+    create_response(
+        data_bundle.ctx,
+        data_bundle.interaction,
+        content,
+        data_bundle.is_ephemeral,
+    )
+    .await;
+    error.kind()
 }
 
 // Data bundling for commands
@@ -128,4 +209,39 @@ impl CommandDataBundle {
     pub fn set_ephemeral(&mut self, is_ephemeral: bool) {
         self.is_ephemeral = is_ephemeral;
     }
+
+    /// The guild the command was used in, every piece of bot config is scoped to it
+    pub fn guild_id(&self) -> Result<GuildId, InteractionError> {
+        match self.interaction.guild_id {
+            Some(guild_id) => Ok(guild_id),
+            None => Err(InteractionError::UnresolvedData(
+                self.interaction.data.name.clone(),
+                "Command was not used inside a server".to_string(),
+            )),
+        }
+    }
+
+    /// What was used, by whom and with which options, for `report_error`
+    pub fn error_context(&self) -> ErrorContext {
+        let mut options = Vec::new();
+        flatten_options(&self.interaction.data.options, "", &mut options);
+
+        ErrorContext {
+            interaction: format!("/{}", self.interaction.data.name),
+            caller: self.interaction.user.clone(),
+            guild_id: self.interaction.guild_id,
+            options,
+        }
+    }
+}
+
+// Subcommands show up as a prefix, e.g. `set major-log-channel channel: 1234`
+fn flatten_options(options: &[CommandDataOption], prefix: &str, out: &mut Vec<(String, String)>) {
+    for option in options {
+        let name = format!("{}{}", prefix, option.name);
+        match &option.value {
+            Some(value) => out.push((name, value.to_string())),
+            None => flatten_options(&option.options, &format!("{} ", name), out),
+        }
+    }
 }
@@ -0,0 +1,71 @@
+use serenity::model::application::interaction::application_command::CommandDataOption;
+use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::prelude::*;
+use std::time::Instant;
+use tracing::{instrument, warn};
+
+use crate::application_commands::find_command;
+use crate::metrics::{record_discord_http_error, record_interaction};
+
+/// Discord shows at most 25 suggestions
+const MAX_CHOICES: usize = 25;
+
+#[instrument(
+    name = "autocomplete",
+    skip_all,
+    fields(
+        command = %interaction.data.name,
+        user_id = interaction.user.id.0,
+        guild_id = interaction.guild_id.map(|id| id.0)
+    )
+)]
+pub async fn handle(ctx: Context, interaction: AutocompleteInteraction) {
+    let started = Instant::now();
+    let name = interaction.data.name.clone();
+
+    let choices = match (
+        find_command(&name),
+        focused_option(&interaction.data.options),
+    ) {
+        (Some(command), Some(option)) => {
+            let partial = option
+                .value
+                .as_ref()
+                .and_then(|value| value.as_str())
+                .unwrap_or_default();
+            command.autocomplete(&option.name, partial)
+        }
+        _ => Vec::new(),
+    };
+
+    let response = interaction
+        .create_autocomplete_response(&ctx.http, |response| {
+            for (name, value) in choices.iter().take(MAX_CHOICES) {
+                response.add_string_choice(name, value);
+            }
+            response
+        })
+        .await;
+
+    // Suggestions are only shown while typing, a failed one needs no report
+    let outcome = match response {
+        Ok(_) => "ok",
+        Err(why) => {
+            record_discord_http_error("autocomplete_response");
+            warn!("Cannot respond to autocomplete: {}", why);
+            "other"
+        }
+    };
+    record_interaction("autocomplete", &name, outcome, started.elapsed());
+}
+
+/// The option being typed in, looking inside whichever subcommand is used
+fn focused_option(options: &[CommandDataOption]) -> Option<&CommandDataOption> {
+    options.iter().find_map(|option| {
+        if option.focused {
+            Some(option)
+        } else {
+            focused_option(&option.options)
+        }
+    })
+}
@@ -7,6 +7,9 @@ pub enum GuildMemberAdditionError {
     #[error("Redis: {0}")]
     RedisError(ErrorMessage),
 
+    #[error("Guild Member Addition Error: {0} is not configured, an admin can set it with `/config set`")]
+    NotConfigured(ErrorMessage),
+
     #[error("Guild Member Addition Error: Invalid Data - `{0}`")]
     InvalidData(ErrorMessage),
 
@@ -23,12 +26,6 @@ pub enum GuildMemberAdditionError {
     Other(ErrorMessage),
 }
 
-#[derive(Error, Debug)]
-pub enum GuildMemberUpdateError {
-    #[error("Guild Member Update Error: Data Missing - `{0}`")]
-    DataMissing(ErrorMessage),
-}
-
 #[derive(Error, Debug)]
 pub enum VoiceStateUpdateError {
     #[error("Voice State Update Error: Data Missing - `{0}`")]
@@ -36,7 +33,4 @@ pub enum VoiceStateUpdateError {
 
     #[error("Voice State Update Error: `{0}`")]
     Other(ErrorMessage),
-
-    #[error("")]
-    Unknown,
 }
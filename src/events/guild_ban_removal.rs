@@ -1,11 +1,25 @@
+use crate::audit_log::wait_for_member_action;
 use crate::log_channel::log_user_unbanned;
-use crate::utils::logging::log_error;
+use crate::log_error;
+use crate::metrics::record_event;
+use serenity::model::guild::audit_log::MemberAction;
 use serenity::model::{prelude::GuildId, user::User};
 use serenity::prelude::*;
+use tracing::instrument;
 
-pub async fn handle(ctx: Context, _guild_id: GuildId, unbanned_user: User) {
-    match log_user_unbanned(&unbanned_user, &ctx).await {
+#[instrument(
+    name = "guild_ban_removal",
+    skip_all,
+    fields(guild_id = guild_id.0, user_id = unbanned_user.id.0)
+)]
+pub async fn handle(ctx: Context, guild_id: GuildId, unbanned_user: User) {
+    record_event("guild_ban_removal");
+
+    let attribution =
+        wait_for_member_action(&ctx, guild_id, unbanned_user.id, &[MemberAction::BanRemove]).await;
+
+    match log_user_unbanned(&unbanned_user, attribution.as_ref(), guild_id, &ctx).await {
         Ok(_) => {}
-        Err(error) => log_error(&error),
+        Err(error) => log_error!(&error),
     };
 }
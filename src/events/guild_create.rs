@@ -0,0 +1,37 @@
+use serenity::model::guild::Guild;
+use serenity::prelude::*;
+
+use crate::events::start_up::{publish_report, setup_guild};
+use crate::log_error;
+use crate::metrics::record_event;
+use crate::store::get_store;
+use crate::temp_voice;
+use crate::voice_sessions;
+use tracing::instrument;
+
+#[instrument(name = "guild_create", skip_all, fields(guild_id = guild.id.0, is_new))]
+pub async fn handle(ctx: Context, guild: Guild, is_new: bool) {
+    record_event("guild_create");
+
+    let store = match get_store(&ctx).await {
+        Ok(store) => store,
+        Err(error) => return log_error!(&error),
+    };
+
+    // Voice states arrive with the guild, catch up on who joined or left while offline
+    if let Err(error) = voice_sessions::reconcile(store.as_ref(), &guild).await {
+        log_error!(&error);
+    }
+    // Temporary channels emptied or deleted while offline
+    if let Err(error) = temp_voice::reconcile(&ctx, store.as_ref(), &guild).await {
+        log_error!(&error);
+    }
+
+    // Guilds the bot was already in are set up from `ready`
+    if !is_new {
+        return;
+    }
+
+    let report = setup_guild(&ctx, store.as_ref(), guild.id, false).await;
+    publish_report(&ctx, report).await;
+}
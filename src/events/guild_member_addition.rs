@@ -1,26 +1,43 @@
+use crate::audit_log::REASON_FOLLOWER_ROLE;
 use crate::events::errors::GuildMemberAdditionError;
 use crate::log_channel::log_user_joined;
-use crate::utils::logging::log_error;
+use crate::log_error;
+use crate::metrics::record_event;
+use crate::readiness::{is_enabled, Feature};
 use serenity::client::Context;
 use serenity::model::guild::Member;
 use serenity::model::prelude::RoleId;
 
-use crate::redis_client;
+use crate::store::{get_store, BotStore};
+use tracing::instrument;
 
+#[instrument(
+    name = "guild_member_addition",
+    skip_all,
+    fields(guild_id = new_member.guild_id.0, user_id = new_member.user.id.0)
+)]
 pub async fn handle(ctx: Context, new_member: Member) {
-    let mut new_member = new_member;
+    record_event("guild_member_addition");
 
-    let mut connection = redis_client::connect();
+    let mut new_member = new_member;
 
-    match give_follower_role(&mut new_member, &mut connection, &ctx).await {
-        Ok(_) => {}
-        Err(error) => log_error(&error),
-    };
+    // Skipped when the startup checks found the follower role can't be handed out
+    if is_enabled(&ctx, new_member.guild_id, Feature::FollowerRole).await {
+        match get_store(&ctx).await {
+            Ok(store) => {
+                match give_follower_role(&mut new_member, store.as_ref(), &ctx).await {
+                    Ok(_) => {}
+                    Err(error) => log_error!(&error),
+                };
+            }
+            Err(error) => log_error!(&error),
+        };
+    }
 
     let user_id = new_member.user.id;
-    match log_user_joined(&user_id, &ctx).await {
+    match log_user_joined(&user_id, new_member.guild_id, &ctx).await {
         Ok(_) => (),
-        Err(error) => log_error(&error),
+        Err(error) => log_error!(&error),
     };
 
     // TODO: Logic for checking for a "log" channel ID and logging this
@@ -29,15 +46,15 @@ pub async fn handle(ctx: Context, new_member: Member) {
 
 async fn give_follower_role(
     new_member: &mut Member,
-    connection: &mut redis::Connection,
+    store: &dyn BotStore,
     ctx: &Context,
 ) -> Result<(), GuildMemberAdditionError> {
-    let follower_role = match redis_client::get_follower_role(connection) {
+    let follower_role = match store.get_follower_role(new_member.guild_id).await {
         Ok(role_id_wrapped) => match role_id_wrapped {
             Some(role_id) => role_id,
             None => {
-                return Err(GuildMemberAdditionError::RedisError(
-                    "Follower role resolved to none".to_string(),
+                return Err(GuildMemberAdditionError::NotConfigured(
+                    "Follower role".to_string(),
                 ))
             }
         },
@@ -63,7 +80,19 @@ async fn give_follower_role(
         }
     };
 
-    let success = new_member.add_role(&ctx.http, follower_role.id).await;
+    // Only adds the one role, so roles given by others while they join are kept
+    let success = ctx
+        .http
+        .add_member_role(
+            new_member.guild_id.0,
+            new_member.user.id.0,
+            follower_role.id.0,
+            Some(REASON_FOLLOWER_ROLE),
+        )
+        .await;
+    if success.is_ok() {
+        new_member.roles.push(follower_role.id);
+    }
 
     let error_reason = match success {
         Ok(_) => "".to_string(),
@@ -1,14 +1,44 @@
+use serenity::model::guild::audit_log::MemberAction;
 use serenity::model::guild::Member;
 use serenity::model::prelude::GuildId;
 use serenity::model::user::User;
 use serenity::prelude::*;
 
-use crate::log_channel::log_user_left;
-use crate::utils::logging::log_error;
+use crate::audit_log::find_member_action;
+use crate::log_channel::{log_user_kicked, log_user_left};
+use crate::log_error;
+use crate::metrics::record_event;
+use tracing::instrument;
 
-pub async fn handle(ctx: Context, _guild_id: GuildId, user: User, _member_data: Option<Member>) {
-    match log_user_left(&user, &ctx).await {
+#[instrument(
+    name = "guild_member_removal",
+    skip_all,
+    fields(guild_id = guild_id.0, user_id = user.id.0)
+)]
+pub async fn handle(ctx: Context, guild_id: GuildId, user: User, _member_data: Option<Member>) {
+    record_event("guild_member_removal");
+
+    // A kick or ban also removes the member, the audit log tells them apart from leaving.
+    // Looked up once, most members leave on their own and never get an entry
+    let attribution = find_member_action(
+        &ctx,
+        guild_id,
+        user.id,
+        &[MemberAction::Kick, MemberAction::BanAdd],
+    )
+    .await;
+
+    let logged = match attribution {
+        Some(attribution) if attribution.is(MemberAction::Kick) => {
+            log_user_kicked(&user, &attribution, guild_id, &ctx).await
+        }
+        // `guild_ban_addition` logs the ban
+        Some(_) => Ok(()),
+        None => log_user_left(&user, guild_id, &ctx).await,
+    };
+
+    match logged {
         Ok(_) => (),
-        Err(error) => log_error(&error),
+        Err(error) => log_error!(&error),
     };
 }
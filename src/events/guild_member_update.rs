@@ -1,23 +1,74 @@
 use serenity::client::Context;
 use serenity::model::prelude::Member;
+use serenity::model::Timestamp;
 
-use crate::log_channel::log_roles_updated;
-use crate::utils::logging::log_error;
+use crate::audit_log::find_member_action;
+use crate::log_channel::{
+    log_avatar_changed, log_member_state, log_member_verified, log_nickname_changed,
+    log_roles_updated, log_server_avatar_changed, log_timeout_changed, log_username_changed,
+    LogChannelError,
+};
+use crate::log_error;
+use crate::metrics::record_event;
 
-use super::errors::GuildMemberUpdateError;
+use chrono::Utc;
+use serenity::model::guild::audit_log::MemberAction;
+use tracing::{debug, instrument};
 
+#[instrument(
+    name = "guild_member_update",
+    skip_all,
+    fields(guild_id = new.guild_id.0, user_id = new.user.id.0)
+)]
 pub async fn handle(old_if_available: Option<Member>, new: Member, ctx: &Context) {
-    let old_roles_state = match old_if_available {
-        Some(state) => state.roles,
+    record_event("guild_member_update");
+
+    // Nothing to compare against when the member wasn't cached yet, log where they are now
+    let old = match old_if_available {
+        Some(old) => old,
         None => {
-            log_error(&GuildMemberUpdateError::DataMissing(
-                "old_roles_state".to_string(),
-            ));
+            debug!("Previous member state not cached, logging the current state");
+            if let Err(error) = log_member_state(
+                &new.user,
+                new.nick.clone(),
+                active_timeout(&new),
+                new.pending,
+                new.guild_id,
+                ctx,
+            )
+            .await
+            {
+                log_error!(&error);
+            }
             return;
         }
     };
 
-    let new_roles_state = new.roles;
+    // Every change gets its own entry, one failing doesn't stop the others
+    let results = [
+        log_role_changes(&old, &new, ctx).await,
+        log_nickname_change(&old, &new, ctx).await,
+        log_server_avatar_change(&old, &new, ctx).await,
+        log_timeout_change(&old, &new, ctx).await,
+        log_verification(&old, &new, ctx).await,
+        log_username_change(&old, &new, ctx).await,
+        log_avatar_change(&old, &new, ctx).await,
+    ];
+
+    for result in results {
+        if let Err(error) = result {
+            log_error!(&error);
+        }
+    }
+}
+
+async fn log_role_changes(
+    old: &Member,
+    new: &Member,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let old_roles_state = &old.roles;
+    let new_roles_state = &new.roles;
 
     let mut new_roles = Vec::new();
     let mut old_roles = Vec::new();
@@ -54,10 +105,117 @@ pub async fn handle(old_if_available: Option<Member>, new: Member, ctx: &Context
         })
         .collect();
 
-    if !old_roles.is_empty() || !new_roles.is_empty() {
-        match log_roles_updated(new.user, new_roles, old_roles, ctx).await {
-            Ok(_) => (),
-            Err(error) => log_error(&error),
-        };
+    if old_roles.is_empty() && new_roles.is_empty() {
+        return Ok(());
+    }
+
+    let attribution =
+        find_member_action(ctx, new.guild_id, new.user.id, &[MemberAction::RoleUpdate]).await;
+
+    log_roles_updated(
+        new.user.clone(),
+        new_roles,
+        old_roles,
+        attribution.as_ref(),
+        new.guild_id,
+        ctx,
+    )
+    .await
+}
+
+async fn log_nickname_change(
+    old: &Member,
+    new: &Member,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    if old.nick == new.nick {
+        return Ok(());
+    }
+
+    log_nickname_changed(
+        &new.user,
+        old.nick.clone(),
+        new.nick.clone(),
+        new.guild_id,
+        ctx,
+    )
+    .await
+}
+
+async fn log_server_avatar_change(
+    old: &Member,
+    new: &Member,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    if old.avatar == new.avatar {
+        return Ok(());
+    }
+
+    log_server_avatar_changed(
+        &new.user,
+        old.avatar_url(),
+        new.avatar_url(),
+        new.guild_id,
+        ctx,
+    )
+    .await
+}
+
+async fn log_timeout_change(
+    old: &Member,
+    new: &Member,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let (was_timed_out, timed_out_until) = (active_timeout(old), active_timeout(new));
+    if was_timed_out == timed_out_until {
+        return Ok(());
     }
+
+    log_timeout_changed(&new.user, timed_out_until, new.guild_id, ctx).await
+}
+
+// An expired timeout stays set until the member is next updated, treat it as none
+fn active_timeout(member: &Member) -> Option<Timestamp> {
+    member
+        .communication_disabled_until
+        .filter(|until| until.unix_timestamp() > Utc::now().timestamp())
+}
+
+async fn log_verification(
+    old: &Member,
+    new: &Member,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    // `pending` clears once the member passes membership screening
+    if !(old.pending && !new.pending) {
+        return Ok(());
+    }
+
+    log_member_verified(&new.user, new.guild_id, ctx).await
+}
+
+// Discord sends global profile changes as a member update in every shared guild,
+// `user_update` only fires for the bot's own account
+async fn log_username_change(
+    old: &Member,
+    new: &Member,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    if old.user.name == new.user.name && old.user.discriminator == new.user.discriminator {
+        return Ok(());
+    }
+
+    log_username_changed(&old.user, &new.user, new.guild_id, ctx).await
+}
+
+async fn log_avatar_change(
+    old: &Member,
+    new: &Member,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    if old.user.avatar == new.user.avatar {
+        return Ok(());
+    }
+
+    log_avatar_changed(&old.user, &new.user, new.guild_id, ctx).await
 }
@@ -1,51 +1,67 @@
 use serenity::{
     builder::CreateComponents,
+    model::id::GuildId,
     model::prelude::interaction::{
         message_component::MessageComponentInteraction, InteractionResponseType,
     },
     prelude::*,
 };
+use std::time::Instant;
+use tracing::{info, instrument, warn};
 
 use crate::{
-    message_component_commands::{errors::ComponentInteractionError, execute_command},
-    utils::logging::log_error,
+    interaction_error::{report_error, ErrorContext, InteractionError},
+    message_component_commands::execute_command,
+    metrics::{record_discord_http_error, record_interaction},
 };
 
-pub async fn handle(ctx: Context, message_component_interaction: MessageComponentInteraction) {
-    let mut data_bundle = MessageComponentDataBundle::new(ctx, message_component_interaction);
+#[instrument(
+    name = "component",
+    skip_all,
+    fields(
+        custom_id = %interaction.data.custom_id,
+        user_id = interaction.user.id.0,
+        guild_id = interaction.guild_id.map(|id| id.0)
+    )
+)]
+pub async fn handle(ctx: Context, interaction: MessageComponentInteraction) {
+    let started = Instant::now();
+    let custom_id = interaction.data.custom_id.clone();
+    let mut data_bundle = MessageComponentDataBundle::new(ctx, interaction);
 
     let content = execute_command(&mut data_bundle).await;
 
-    if let Ok(content) = content {
-        let msg = content.message;
-        let modal = content.modal;
-        match modal {
-            Some(modal) => {
-                create_modal_response(data_bundle.ctx, data_bundle.interaction, modal).await
-            }
-            None => {
-                if let Some(msg) = msg {
-                    create_response(
-                        data_bundle.ctx,
-                        data_bundle.interaction,
-                        msg,
-                        data_bundle.is_ephemeral,
-                    )
-                    .await
+    let outcome = match content {
+        Ok(content) => {
+            let msg = content.message;
+            let modal = content.modal;
+            match modal {
+                Some(modal) => {
+                    create_modal_response(data_bundle.ctx, data_bundle.interaction, modal).await
+                }
+                None => {
+                    if let Some(msg) = msg {
+                        create_response(
+                            data_bundle.ctx,
+                            data_bundle.interaction,
+                            msg,
+                            data_bundle.is_ephemeral,
+                        )
+                        .await
+                    }
                 }
             }
+            "ok"
         }
-    } else if let Err(error) = content {
-        log_error(&error);
-        let content = match_error(error);
-        create_response(
-            data_bundle.ctx,
-            data_bundle.interaction,
-            content,
-            data_bundle.is_ephemeral,
-        )
-        .await;
-    }
+        Err(error) => respond_with_error(data_bundle, error).await,
+    };
+    record_interaction("component", &custom_id, outcome, started.elapsed());
+
+    info!(
+        outcome,
+        latency_ms = started.elapsed().as_millis() as u64,
+        "Component handled"
+    );
 }
 
 async fn create_response(
@@ -64,7 +80,8 @@ async fn create_response(
         })
         .await
     {
-        println!("Cannot respond to message component: {}", why);
+        record_discord_http_error("interaction_response");
+        warn!("Cannot respond to message component: {}", why);
     }
 }
 
@@ -85,18 +102,25 @@ async fn create_modal_response(
         })
         .await
     {
-        println!("Cannot respond to message component: {}", why);
+        record_discord_http_error("interaction_response");
+        warn!("Cannot respond to message component: {}", why);
     }
 }
 
-fn match_error(error: ComponentInteractionError) -> String {
-    match error {
-        ComponentInteractionError::ArgumentMissing(_) => "Missing an option...".to_string(),
-        ComponentInteractionError::RedisError(content) => content,
-        ComponentInteractionError::Other(content) => content,
-        ComponentInteractionError::CacheError(content) => content,
-        ComponentInteractionError::UnresolvedData(_, content) => content,
-    }
+async fn respond_with_error(
+    data_bundle: MessageComponentDataBundle,
+    error: InteractionError,
+) -> &'static str {
+    let context = data_bundle.error_context();
+    let content = report_error(&data_bundle.ctx, &error, &context).await;
+    create_response(
+        data_bundle.ctx,
+        data_bundle.interaction,
+        content,
+        data_bundle.is_ephemeral,
+    )
+    .await;
+    error.kind()
 }
 
 // Data Bundle for Message Components
@@ -118,6 +142,35 @@ impl MessageComponentDataBundle {
     pub fn set_ephemeral(&mut self, is_ephemeral: bool) {
         self.is_ephemeral = is_ephemeral;
     }
+
+    /// The guild the component was used in, every piece of bot config is scoped to it
+    pub fn guild_id(&self) -> Result<GuildId, InteractionError> {
+        match self.interaction.guild_id {
+            Some(guild_id) => Ok(guild_id),
+            None => Err(InteractionError::UnresolvedData(
+                self.interaction.data.custom_id.clone(),
+                "Component was not used inside a server".to_string(),
+            )),
+        }
+    }
+
+    /// What was used, by whom and with which values, for `report_error`
+    pub fn error_context(&self) -> ErrorContext {
+        let options = self
+            .interaction
+            .data
+            .values
+            .iter()
+            .map(|value| ("value".to_string(), value.clone()))
+            .collect();
+
+        ErrorContext {
+            interaction: format!("component `{}`", self.interaction.data.custom_id),
+            caller: self.interaction.user.clone(),
+            guild_id: self.interaction.guild_id,
+            options,
+        }
+    }
 }
 
 // Response Bundle for Message Components
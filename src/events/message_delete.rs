@@ -0,0 +1,88 @@
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, MessageId};
+use serenity::prelude::*;
+
+use crate::application_commands::prune::take_prune;
+use crate::log_channel::{log_message_deleted, log_messages_bulk_deleted};
+use crate::log_error;
+use crate::metrics::record_event;
+use tracing::instrument;
+
+#[instrument(
+    name = "message_delete",
+    skip_all,
+    fields(channel_id = channel_id.0, message_id = message_id.0)
+)]
+pub async fn handle(
+    ctx: Context,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    guild_id: Option<GuildId>,
+) {
+    record_event("message_delete");
+
+    let Some(guild_id) = guild_id else {
+        return;
+    };
+
+    // Deleted messages stay in the cache, so the content can still be read here
+    let message = ctx.cache.message(channel_id, message_id);
+    let pruned_by = take_prune(&ctx, channel_id).await;
+
+    // Bots clean up their own messages all the time, only `/prune` is worth logging
+    if pruned_by.is_none() && matches!(&message, Some(message) if message.author.bot) {
+        return;
+    }
+
+    match log_message_deleted(
+        channel_id,
+        message_id,
+        message.as_ref(),
+        pruned_by,
+        guild_id,
+        &ctx,
+    )
+    .await
+    {
+        Ok(_) => (),
+        Err(error) => log_error!(&error),
+    };
+}
+
+#[instrument(
+    name = "message_delete_bulk",
+    skip_all,
+    fields(channel_id = channel_id.0, count = message_ids.len())
+)]
+pub async fn handle_bulk(
+    ctx: Context,
+    channel_id: ChannelId,
+    message_ids: Vec<MessageId>,
+    guild_id: Option<GuildId>,
+) {
+    record_event("message_delete_bulk");
+
+    let Some(guild_id) = guild_id else {
+        return;
+    };
+
+    let messages: Vec<Message> = message_ids
+        .iter()
+        .filter_map(|message_id| ctx.cache.message(channel_id, *message_id))
+        .collect();
+    let pruned_by = take_prune(&ctx, channel_id).await;
+
+    match log_messages_bulk_deleted(
+        channel_id,
+        &message_ids,
+        &messages,
+        pruned_by,
+        guild_id,
+        &ctx,
+    )
+    .await
+    {
+        Ok(_) => (),
+        Err(error) => log_error!(&error),
+    };
+}
@@ -0,0 +1,60 @@
+use serenity::model::channel::Message;
+use serenity::model::event::MessageUpdateEvent;
+use serenity::prelude::*;
+
+use crate::log_channel::log_message_edited;
+use crate::log_error;
+use crate::metrics::record_event;
+use tracing::instrument;
+
+#[instrument(
+    name = "message_update",
+    skip_all,
+    fields(channel_id = event.channel_id.0, message_id = event.id.0)
+)]
+pub async fn handle(
+    ctx: Context,
+    old_if_available: Option<Message>,
+    new: Option<Message>,
+    event: MessageUpdateEvent,
+) {
+    record_event("message_update");
+
+    // Embeds being unfurled also send an update, only content edits are logged
+    let Some(content) = event.content else {
+        return;
+    };
+    let Some(guild_id) = event.guild_id else {
+        return;
+    };
+
+    let author = match new.map(|message| message.author).or(event.author) {
+        Some(author) => author,
+        None => return,
+    };
+    if author.bot {
+        return;
+    }
+
+    let before = old_if_available.map(|message| message.content);
+    if before.as_deref() == Some(content.as_str()) {
+        return;
+    }
+
+    let link = event.id.link(event.channel_id, Some(guild_id));
+
+    match log_message_edited(
+        &author,
+        before,
+        &content,
+        link,
+        event.channel_id,
+        guild_id,
+        &ctx,
+    )
+    .await
+    {
+        Ok(_) => (),
+        Err(error) => log_error!(&error),
+    };
+}
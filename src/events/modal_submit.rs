@@ -1,36 +1,50 @@
 use serenity::model::application::interaction::modal::ModalSubmitInteraction;
+use serenity::model::prelude::component::ActionRowComponent;
 use serenity::model::prelude::interaction::InteractionResponseType;
 use serenity::prelude::*;
+use std::time::Instant;
+use tracing::{info, instrument, warn};
 
-use crate::modal_submits::errors::ModalError;
+use crate::interaction_error::{report_error, ErrorContext, InteractionError};
+use crate::metrics::{record_discord_http_error, record_interaction};
 use crate::modal_submits::process_modal_data;
-use crate::utils::logging::log_error;
 
-pub async fn handle(ctx: Context, modal_submit_interaction: ModalSubmitInteraction) {
-    let mut data_bundle = ModalDataBundle::new(ctx, modal_submit_interaction);
-    // println!("{:#?}", &data_bundle.interaction.data);
+#[instrument(
+    name = "modal",
+    skip_all,
+    fields(
+        custom_id = %interaction.data.custom_id,
+        user_id = interaction.user.id.0,
+        guild_id = interaction.guild_id.map(|id| id.0)
+    )
+)]
+pub async fn handle(ctx: Context, interaction: ModalSubmitInteraction) {
+    let started = Instant::now();
+    let custom_id = interaction.data.custom_id.clone();
+    let mut data_bundle = ModalDataBundle::new(ctx, interaction);
 
     let content = process_modal_data(&mut data_bundle).await;
 
-    if let Ok(content) = content {
-        create_response(
-            data_bundle.ctx,
-            data_bundle.interaction,
-            content,
-            data_bundle.is_ephemeral,
-        )
-        .await;
-    } else if let Err(error) = content {
-        log_error(&error);
-        let content = match_error(error);
-        create_response(
-            data_bundle.ctx,
-            data_bundle.interaction,
-            content,
-            data_bundle.is_ephemeral,
-        )
-        .await;
-    }
+    let outcome = match content {
+        Ok(content) => {
+            create_response(
+                data_bundle.ctx,
+                data_bundle.interaction,
+                content,
+                data_bundle.is_ephemeral,
+            )
+            .await;
+            "ok"
+        }
+        Err(error) => respond_with_error(data_bundle, error).await,
+    };
+    record_interaction("modal", &custom_id, outcome, started.elapsed());
+
+    info!(
+        outcome,
+        latency_ms = started.elapsed().as_millis() as u64,
+        "Modal handled"
+    );
 }
 
 async fn create_response(
@@ -49,17 +63,22 @@ async fn create_response(
         })
         .await
     {
-        println!("Cannot respond to modal submition: {}", why);
+        record_discord_http_error("interaction_response");
+        warn!("Cannot respond to modal submition: {}", why);
     }
 }
 
-fn match_error(error: ModalError) -> String {
-    match error {
-        ModalError::ArgumentMissing(_) => "Missing an option...".to_string(),
-        ModalError::RedisError(content) => content,
-        ModalError::Other(content) => content,
-        ModalError::UnresolvedData(_, content) => content,
-    }
+async fn respond_with_error(data_bundle: ModalDataBundle, error: InteractionError) -> &'static str {
+    let context = data_bundle.error_context();
+    let content = report_error(&data_bundle.ctx, &error, &context).await;
+    create_response(
+        data_bundle.ctx,
+        data_bundle.interaction,
+        content,
+        data_bundle.is_ephemeral,
+    )
+    .await;
+    error.kind()
 }
 
 // Data bundling
@@ -81,4 +100,28 @@ impl ModalDataBundle {
     pub fn set_ephemeral(&mut self, is_ephemeral: bool) {
         self.is_ephemeral = is_ephemeral;
     }
+
+    /// What was submitted and by whom, for `report_error`
+    pub fn error_context(&self) -> ErrorContext {
+        let options = self
+            .interaction
+            .data
+            .components
+            .iter()
+            .flat_map(|action_row| action_row.components.iter())
+            .filter_map(|component| match component {
+                ActionRowComponent::InputText(input_text) => {
+                    Some((input_text.custom_id.clone(), input_text.value.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        ErrorContext {
+            interaction: format!("modal `{}`", self.interaction.data.custom_id),
+            caller: self.interaction.user.clone(),
+            guild_id: self.interaction.guild_id,
+            options,
+        }
+    }
 }
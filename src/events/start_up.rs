@@ -3,13 +3,24 @@ use serenity::model::gateway::Ready;
 use serenity::model::id::GuildId;
 use serenity::model::prelude::{ChannelId, GuildChannel, Role, RoleId};
 
-use crate::application_commands::{self as sc, guild_commands_reg};
+use crate::application_commands::{self as sc, GLOBAL_COMMANDS, GUILD_COMMANDS};
 
 use serenity::model::prelude::command::Command;
 use std::collections::HashMap;
 use std::env;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use crate::redis_client::{self, check_master_admin, set_guild_id};
+use crate::log_channel::{log_readiness_report, queue};
+use crate::log_error;
+use crate::metrics::record_event;
+use crate::readiness::{has_report, record_report, Feature, GuildReport};
+use crate::store::errors::StoreError;
+use crate::store::{get_store, BotStore};
+use tracing::{info, instrument, warn};
+
+// `ready` fires once per shard and again after every reconnect,
+// global commands only need registering the first time
+static GLOBAL_COMMANDS_REGISTERED: AtomicBool = AtomicBool::new(false);
 
 struct LocalGuild {
     role_list: HashMap<RoleId, Role>,
@@ -18,22 +29,15 @@ struct LocalGuild {
 }
 
 impl LocalGuild {
-    async fn new(guild_id: &GuildId, ctx: &Context) -> Self {
-        let guild_roles = guild_id
-            .roles(&ctx.http)
-            .await
-            .expect("Query to retrieve guild roles failed");
-
-        let guild_channels = guild_id
-            .channels(&ctx.http)
-            .await
-            .expect("Query to retrieve guild channels failed");
-
-        Self {
+    async fn new(guild_id: &GuildId, ctx: &Context) -> Result<Self, serenity::Error> {
+        let guild_roles = guild_id.roles(&ctx.http).await?;
+        let guild_channels = guild_id.channels(&ctx.http).await?;
+
+        Ok(Self {
             role_list: guild_roles,
             channel_list: guild_channels,
             // guild_id: *guild_id,
-        }
+        })
     }
 
     fn role_exists(&self, role_id: &RoleId) -> bool {
@@ -43,106 +47,487 @@ impl LocalGuild {
     fn channel_exists(&self, channel_id: &ChannelId) -> bool {
         self.channel_list.get(channel_id).is_some()
     }
+
+    fn role_position(&self, role_id: u64) -> Option<i64> {
+        self.role_list
+            .get(&RoleId(role_id))
+            .map(|role| role.position)
+    }
 }
 
+#[instrument(
+    name = "ready",
+    skip_all,
+    fields(user = %ready.user.name, shard = ctx.shard_id, guilds = ready.guilds.len())
+)]
 pub async fn handle(ctx: Context, ready: Ready) {
-    println!("{} is connected!", ready.user.name);
-
-    // Create a GuildId by parsing the Environmental Variable GUILD_ID
-    let guild_id = GuildId(
-        env::var("GUILD_ID")
-            .expect("Expected GUILD_ID in environment")
-            .parse()
-            .expect("GUILD_ID must be an integer"),
+    record_event("ready");
+
+    info!(
+        "{} is connected on shard {} of {}!",
+        ready.user.name,
+        ctx.shard_id,
+        ctx.cache.shard_count()
     );
 
-    // Open a connection to Redis
-    let mut connection = redis_client::connect();
+    // Post the logs a previous run left in the queue
+    queue::start(&ctx);
+
+    // Grab the configured store
+    let store = match get_store(&ctx).await {
+        Ok(store) => store,
+        Err(error) => return log_error!(&error),
+    };
+
+    // The guild from GUILD_ID is the one the bot served before it was multi-guild,
+    // it keeps its environment config and adopts the old global data
+    let legacy_guild_id = env::var("GUILD_ID")
+        .ok()
+        .and_then(|id| id.parse::<u64>().ok())
+        .map(GuildId);
+
+    for guild in ready.guilds.iter() {
+        // Set up on an earlier `ready`, `/bot-status` re-runs the checks when needed
+        if has_report(&ctx, guild.id).await {
+            continue;
+        }
+
+        let is_legacy_guild = legacy_guild_id == Some(guild.id);
+        let report = setup_guild(&ctx, store.as_ref(), guild.id, is_legacy_guild).await;
+        publish_report(&ctx, report).await;
+    }
+
+    if GLOBAL_COMMANDS_REGISTERED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    // Register global commands
+    let global_commands = Command::set_global_application_commands(&ctx.http, |commands| {
+        GLOBAL_COMMANDS.register(commands)
+    })
+    .await;
+
+    // Let the next `ready` try again
+    if global_commands.is_err() {
+        GLOBAL_COMMANDS_REGISTERED.store(false, Ordering::SeqCst);
+    }
+
+    if let Ok(registered) = global_commands.as_ref() {
+        for name in GLOBAL_COMMANDS.unhandled(registered) {
+            warn!("Global command `{}` has no handler", name);
+        }
+    }
+
+    // For debugging
+    sc::utils::check_global_command_reg_verbose(global_commands);
+}
+
+/// Prepare `guild_id` for use and report what does and doesn't work there.
+///
+/// Nothing in here panics, a failed step shows up in the report instead
+pub async fn setup_guild(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    is_legacy_guild: bool,
+) -> GuildReport {
+    if let Err(error) = prepare_store(store, guild_id, is_legacy_guild).await {
+        log_error!(&error);
+    }
+
+    match LocalGuild::new(&guild_id, ctx).await {
+        Ok(guild) => guild.apply_first_run_defaults(store, guild_id).await,
+        Err(error) => log_error!(&error),
+    }
 
-    check_master_admin(&mut connection)
-        .expect("check_master_admin() failed, try checking your Redis Connection");
+    register_commands(ctx, &guild_id).await;
 
-    set_guild_id(&mut connection, guild_id.to_string()).expect("Redis: Could not set `guild id`");
+    check_guild(ctx, store, guild_id).await
+}
 
-    let guild = LocalGuild::new(&guild_id, &ctx).await;
+/// Print the report, post it to the major log channel and keep it for `/bot-status`
+pub async fn publish_report(ctx: &Context, report: GuildReport) {
+    info!(
+        "Readiness report for guild {}:\n{}",
+        report.guild_id,
+        report.render()
+    );
 
-    guild.check_follower_role(&mut connection).await;
-    guild.check_log_channel(&mut connection).await;
+    if report.is_enabled(Feature::MajorLogChannel) {
+        if let Err(error) = log_readiness_report(&report, ctx).await {
+            log_error!(&error);
+        }
+    }
 
-    register_commands(&ctx, &guild_id).await;
+    record_report(ctx, report).await;
+}
+
+async fn prepare_store(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    is_legacy_guild: bool,
+) -> Result<(), StoreError> {
+    store.add_guild(guild_id).await?;
+
+    if is_legacy_guild {
+        store.adopt_legacy_data(guild_id).await?;
+    }
+
+    Ok(())
 }
 
 async fn register_commands(ctx: &Context, guild_id: &GuildId) {
     // Register guild commands
-    let guild_commands = GuildId::set_application_commands(guild_id, &ctx.http, move |commands| {
-        guild_commands_reg(commands)
+    let guild_commands = GuildId::set_application_commands(guild_id, &ctx.http, |commands| {
+        GUILD_COMMANDS.register(commands)
     })
     .await;
 
-    // Register global commands
-    let global_command =
-        Command::create_global_application_command(&ctx.http, sc::ping::setup()).await;
+    if let Ok(registered) = guild_commands.as_ref() {
+        for name in GUILD_COMMANDS.unhandled(registered) {
+            warn!("Guild command `{}` in {} has no handler", name, guild_id);
+        }
+    }
 
     // For debugging
-    sc::utils::check_command_reg_verbose(guild_commands, global_command);
+    sc::utils::check_guild_command_reg_verbose(guild_id, guild_commands);
+}
+
+/// Run every readiness check against `guild_id`
+pub async fn check_guild(ctx: &Context, store: &dyn BotStore, guild_id: GuildId) -> GuildReport {
+    let mut report = GuildReport::new(guild_id);
+
+    match store.ping().await {
+        Ok(_) => report.passed("Store", "Reachable"),
+        Err(error) => {
+            report.failed(
+                "Store",
+                format!("Unreachable ({}), settings could not be checked", error),
+                &[],
+            );
+            return report;
+        }
+    }
+
+    let guild = match LocalGuild::new(&guild_id, ctx).await {
+        Ok(guild) => guild,
+        Err(error) => {
+            report.failed(
+                "Guild access",
+                format!("Could not read roles and channels ({})", error),
+                &Feature::ALL,
+            );
+            return report;
+        }
+    };
+    report.passed(
+        "Shard",
+        format!(
+            "Served by shard {} of {}",
+            ctx.shard_id,
+            ctx.cache.shard_count()
+        ),
+    );
+
+    report.passed(
+        "Guild access",
+        format!(
+            "{} roles, {} channels",
+            guild.role_list.len(),
+            guild.channel_list.len()
+        ),
+    );
+
+    let follower_role = check_setting(
+        &mut report,
+        "Follower role",
+        store.get_follower_role(guild_id).await,
+        |id| guild.role_exists(&RoleId(id)),
+        |id| format!("<@&{}>", id),
+        Feature::FollowerRole,
+    );
+
+    check_setting(
+        &mut report,
+        "Major log channel",
+        store.get_major_log_channel(guild_id).await,
+        |id| guild.channel_exists(&ChannelId(id)),
+        |id| format!("<#{}>", id),
+        Feature::MajorLogChannel,
+    );
+
+    check_setting(
+        &mut report,
+        "Minor log channel",
+        store.get_minor_log_channel(guild_id).await,
+        |id| guild.channel_exists(&ChannelId(id)),
+        |id| format!("<#{}>", id),
+        Feature::MinorLogChannel,
+    );
+
+    guild
+        .check_role_management(ctx, store, guild_id, follower_role, &mut report)
+        .await;
+
+    report
+}
+
+/// Check a stored role or channel setting points at something in the guild,
+/// returning the id when it does
+fn check_setting(
+    report: &mut GuildReport,
+    name: &str,
+    configured: Result<Option<String>, StoreError>,
+    exists: impl Fn(u64) -> bool,
+    mention: impl Fn(u64) -> String,
+    feature: Feature,
+) -> Option<u64> {
+    let configured = match configured {
+        Ok(x) => x,
+        Err(error) => {
+            report.failed(name, format!("Could not be read ({})", error), &[feature]);
+            return None;
+        }
+    };
+
+    let id = match configured {
+        Some(id) => id,
+        None => {
+            report.failed(
+                name,
+                "Not configured, set it with `/config set`",
+                &[feature],
+            );
+            return None;
+        }
+    };
+
+    match id.parse::<u64>() {
+        Ok(id) if exists(id) => {
+            report.passed(name, mention(id));
+            Some(id)
+        }
+        _ => {
+            report.failed(
+                name,
+                format!("{} no longer exists, update it with `/config set`", id),
+                &[feature],
+            );
+            None
+        }
+    }
 }
 
 impl LocalGuild {
-    async fn check_follower_role(&self, connection: &mut redis::Connection) {
-        let follower_id = RoleId(
-            env::var("ROLE_FOLLOWER_ID")
-                .expect("Expected ROLE_FOLLOWER_ID in environment")
-                .parse()
-                .expect("ROLE_FOLLOWER_ID must be an integer"),
-        );
-
-        if self.role_exists(&follower_id) {
-            println!("Follower role found: {}", follower_id);
+    /// The bot needs Manage Roles, and its highest role has to sit above
+    /// every role it hands out
+    async fn check_role_management(
+        &self,
+        ctx: &Context,
+        store: &dyn BotStore,
+        guild_id: GuildId,
+        follower_role: Option<u64>,
+        report: &mut GuildReport,
+    ) {
+        let bot_member = match guild_id.member(ctx, ctx.cache.current_user_id()).await {
+            Ok(x) => x,
+            Err(error) => {
+                report.failed(
+                    "Manage Roles",
+                    format!("Could not read the bot's roles ({})", error),
+                    &[Feature::FollowerRole, Feature::GameRoles],
+                );
+                return;
+            }
+        };
+
+        // `@everyone` shares its id with the guild and applies to every member
+        let bot_roles: Vec<&Role> = self
+            .role_list
+            .values()
+            .filter(|role| role.id.0 == guild_id.0 || bot_member.roles.contains(&role.id))
+            .collect();
+
+        let can_view_audit_log = bot_roles
+            .iter()
+            .any(|role| role.permissions.view_audit_log() || role.permissions.administrator());
+        if can_view_audit_log {
+            report.passed("View Audit Log", "Granted");
         } else {
-            panic!("Follower role not in guild, please add one!");
+            report.warning(
+                "View Audit Log",
+                "Missing, logs can't show who banned, kicked or changed the roles of a member",
+            );
+        }
+
+        let can_manage_roles = bot_roles
+            .iter()
+            .any(|role| role.permissions.manage_roles() || role.permissions.administrator());
+        if !can_manage_roles {
+            report.failed(
+                "Manage Roles",
+                "The bot is missing the Manage Roles permission",
+                &[Feature::FollowerRole, Feature::GameRoles],
+            );
+            return;
+        }
+        report.passed("Manage Roles", "Granted");
+
+        let top_position = bot_roles
+            .iter()
+            .map(|role| role.position)
+            .max()
+            .unwrap_or(0);
+        let is_below_bot =
+            |id: u64| matches!(self.role_position(id), Some(position) if position < top_position);
+
+        let mut hierarchy_ok = true;
+
+        if let Some(follower_id) = follower_role {
+            if !is_below_bot(follower_id) {
+                hierarchy_ok = false;
+                report.failed(
+                    "Role hierarchy",
+                    format!("<@&{}> is above the bot's highest role", follower_id),
+                    &[Feature::FollowerRole],
+                );
+            }
         }
 
-        match redis_client::set_follower_role(connection, follower_id.to_string()) {
-            Ok(_) => (),
-            Err(e) => panic!("{}", e),
+        let games = match store.list_games(guild_id).await {
+            Ok(x) => x,
+            Err(error) => {
+                report.failed(
+                    "Game roles",
+                    format!("Could not be read ({})", error),
+                    &[Feature::GameRoles],
+                );
+                return;
+            }
+        };
+
+        let (game_roles, deleted_games): (Vec<u64>, Vec<u64>) = games
+            .iter()
+            .filter_map(|game| game.parse::<u64>().ok())
+            .partition(|id| self.role_exists(&RoleId(*id)));
+
+        if !deleted_games.is_empty() {
+            report.warning(
+                "Game roles",
+                format!(
+                    "{} game role(s) no longer exist, `/list-games` will clean them up",
+                    deleted_games.len()
+                ),
+            );
+        }
+
+        let games_above: Vec<String> = game_roles
+            .into_iter()
+            .filter(|id| !is_below_bot(*id))
+            .map(|id| format!("<@&{}>", id))
+            .collect();
+
+        if !games_above.is_empty() {
+            hierarchy_ok = false;
+            report.failed(
+                "Role hierarchy",
+                format!(
+                    "Game roles above the bot's highest role: {}",
+                    games_above.join(", ")
+                ),
+                &[Feature::GameRoles],
+            );
+        }
+
+        if hierarchy_ok {
+            report.passed(
+                "Role hierarchy",
+                "The bot's highest role is above the follower and game roles",
+            );
         }
     }
 
-    async fn check_log_channel(&self, connection: &mut redis::Connection) {
-        let major_log_channel_id = ChannelId(
-            env::var("MAJOR_LOG_CHANNEL_ID")
-                .expect("Expected MAJOR_LOG_CHANNEL_ID in environment")
-                .parse()
-                .expect("MAJOR_LOG_CHANNEL_ID must be an integer"),
-        );
+    async fn apply_first_run_defaults(&self, store: &dyn BotStore, guild_id: GuildId) {
+        match store.claim_first_run(guild_id).await {
+            Ok(true) => {}
+            Ok(false) => return,
+            Err(error) => return log_error!(&error),
+        }
 
-        let minor_log_channel_id = ChannelId(
-            env::var("MINOR_LOG_CHANNEL_ID")
-                .expect("Expected MINOR_LOG_CHANNEL_ID in environment")
-                .parse()
-                .expect("MINOR_LOG_CHANNEL_ID must be an integer"),
-        );
+        match store.get_follower_role(guild_id).await {
+            Ok(configured) => {
+                let default =
+                    self.first_run_default("Follower role", configured, "ROLE_FOLLOWER_ID", |id| {
+                        self.role_exists(&RoleId(id))
+                    });
 
-        if self.channel_exists(&major_log_channel_id) {
-            println!("Major log channel found: {}", major_log_channel_id);
-        } else {
-            panic!("Major log channel not found, please add one!");
+                if let Some(follower_id) = default {
+                    if let Err(error) = store.set_follower_role(guild_id, follower_id).await {
+                        log_error!(&error);
+                    }
+                }
+            }
+            Err(error) => log_error!(&error),
         }
 
-        if self.channel_exists(&minor_log_channel_id) {
-            println!("Minor log channel found: {}", minor_log_channel_id);
-        } else {
-            panic!("Minor log channel not found, please add one!");
+        match store.get_major_log_channel(guild_id).await {
+            Ok(configured) => {
+                let default = self.first_run_default(
+                    "Major log channel",
+                    configured,
+                    "MAJOR_LOG_CHANNEL_ID",
+                    |id| self.channel_exists(&ChannelId(id)),
+                );
+
+                if let Some(channel_id) = default {
+                    if let Err(error) = store.set_major_log_channel(guild_id, channel_id).await {
+                        log_error!(&error);
+                    }
+                }
+            }
+            Err(error) => log_error!(&error),
         }
 
-        match redis_client::set_major_log_channel(connection, major_log_channel_id.to_string()) {
-            Ok(_) => (),
-            Err(e) => panic!("{}", e),
+        match store.get_minor_log_channel(guild_id).await {
+            Ok(configured) => {
+                let default = self.first_run_default(
+                    "Minor log channel",
+                    configured,
+                    "MINOR_LOG_CHANNEL_ID",
+                    |id| self.channel_exists(&ChannelId(id)),
+                );
+
+                if let Some(channel_id) = default {
+                    if let Err(error) = store.set_minor_log_channel(guild_id, channel_id).await {
+                        log_error!(&error);
+                    }
+                }
+            }
+            Err(error) => log_error!(&error),
+        }
+    }
+
+    /// Return the id to store when the setting was never configured and
+    /// `env_key` points at something in this guild.
+    ///
+    /// Environment variables are only first-run defaults, `/config` owns the value afterwards
+    fn first_run_default(
+        &self,
+        label: &str,
+        configured: Option<String>,
+        env_key: &str,
+        exists: impl Fn(u64) -> bool,
+    ) -> Option<String> {
+        if configured.is_some() {
+            return None;
         }
 
-        match redis_client::set_minor_log_channel(connection, minor_log_channel_id.to_string()) {
-            Ok(_) => (),
-            Err(e) => panic!("{}", e),
+        match env::var(env_key).ok().and_then(|id| id.parse::<u64>().ok()) {
+            Some(id) if exists(id) => {
+                info!("{} set from {}: {}", label, env_key, id);
+                Some(id.to_string())
+            }
+            _ => None,
         }
     }
 }
@@ -1,62 +1,331 @@
-use crate::log_channel::{log_voice_chat_joined, log_voice_chat_left, log_voice_chat_moved};
-use serenity::model::id::ChannelId;
+use crate::audit_log::wait_for_member_action;
+use crate::log_channel::{
+    log_voice_chat_joined, log_voice_chat_left, log_voice_chat_moved, log_voice_state_changed,
+    LogType,
+};
+use serenity::model::guild::audit_log::MemberAction;
+use serenity::model::guild::Member;
+use serenity::model::id::{ChannelId, GuildId};
 use serenity::model::user::User;
 use serenity::model::voice::VoiceState;
 use serenity::prelude::*;
 
-use crate::utils::logging::log_error;
+use crate::log_error;
+use crate::metrics::record_event;
+use crate::store::get_store;
+use crate::temp_voice;
+use crate::voice_sessions::{self, VoiceSession};
 
 use super::errors::VoiceStateUpdateError;
+use tracing::{debug, instrument};
 
+#[derive(PartialEq, Eq, Debug)]
 pub enum VoiceAction {
     UserJoinedChannel,
     UserLeftChannel,
     UserMovedChannel,
+    /// Muted or unmuted by a moderator, `true` when muted
+    ServerMute(bool),
+    ServerDeafen(bool),
+    /// Muted or unmuted themselves, `true` when muted
+    SelfMute(bool),
+    SelfDeafen(bool),
+    /// Started or stopped going live, `true` when started
+    Stream(bool),
+    /// Turned the camera on or off, `true` when turned on
+    Video(bool),
     Unknown,
 }
 
+#[instrument(
+    name = "voice_state_update",
+    skip_all,
+    fields(guild_id = new.guild_id.map(|id| id.0), user_id = new.user_id.0)
+)]
 pub async fn handle(ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+    let guild_id = match new.guild_id {
+        Some(guild_id) => guild_id,
+        None => {
+            log_error!(&VoiceStateUpdateError::DataMissing(
+                "GuildId from new VoiceState missing".to_string(),
+            ));
+            return;
+        }
+    };
+
     let action = VoiceAction::new(&old, &new);
+
+    // Same channel, something else about the voice state changed
+    if let VoiceAction::Unknown = action {
+        let changes = match &old {
+            Some(old) => VoiceAction::changes(old, &new),
+            None => Vec::new(),
+        };
+        if changes.is_empty() {
+            record_event(action.event_name());
+            debug!("Voice state changed without anything to log");
+        }
+
+        for change in changes {
+            record_event(change.event_name());
+            log_change(&ctx, &change, &new, guild_id).await;
+        }
+        return;
+    }
+
+    record_event(action.event_name());
+
+    let member = new.member.clone();
     let data = match action {
         VoiceAction::UserJoinedChannel => VoiceAction::joined_channel(new),
         VoiceAction::UserLeftChannel => VoiceAction::left_channel(old, new),
         VoiceAction::UserMovedChannel => VoiceAction::moved_channel(old, new),
-        VoiceAction::Unknown => Err(VoiceStateUpdateError::Unknown),
+        _ => return,
     };
 
-    match data {
-        Ok(data) => send_log(ctx, action, data).await,
-        Err(e) => log_error(&e),
-    }
+    let data = match data {
+        Ok(data) => data,
+        Err(e) => return log_error!(&e),
+    };
+
+    // Move them out of a hub first, the move comes back as its own event
+    manage_temp_channels(&ctx, &action, member.as_ref(), &data, guild_id).await;
+
+    // Sessions are tracked even when the voice logs are muted
+    let session = track_session(&ctx, &action, &data, guild_id).await;
+    send_log(ctx, action, data, session, guild_id).await
 }
 
 type LogData = (User, Vec<ChannelId>);
 
-pub async fn send_log(ctx: Context, log_type: VoiceAction, data: LogData) {
+/// Create a temporary channel when a hub was joined, delete the ones left empty
+async fn manage_temp_channels(
+    ctx: &Context,
+    action: &VoiceAction,
+    member: Option<&Member>,
+    data: &LogData,
+    guild_id: GuildId,
+) {
+    let store = match get_store(ctx).await {
+        Ok(store) => store,
+        Err(error) => return log_error!(&error),
+    };
+
+    let user_id = data.0.id;
+    let (joined, left) = match action {
+        VoiceAction::UserJoinedChannel => (Some(data.1[0]), None),
+        VoiceAction::UserLeftChannel => (None, Some(data.1[0])),
+        VoiceAction::UserMovedChannel => (Some(data.1[0]), Some(data.1[1])),
+        _ => return,
+    };
+
+    if let Some(channel_id) = left {
+        if let Err(error) =
+            temp_voice::left(ctx, store.as_ref(), guild_id, user_id, channel_id).await
+        {
+            log_error!(&error);
+        }
+    }
+
+    if let (Some(channel_id), Some(member)) = (joined, member) {
+        if let Err(error) =
+            temp_voice::joined(ctx, store.as_ref(), guild_id, member, channel_id).await
+        {
+            log_error!(&error);
+        }
+    }
+}
+
+async fn log_change(ctx: &Context, change: &VoiceAction, new: &VoiceState, guild_id: GuildId) {
+    let (log_type, title) = match change.describe() {
+        Some(x) => x,
+        None => return,
+    };
+
+    let user = match &new.member {
+        Some(member) => member.user.clone(),
+        None => {
+            return log_error!(&VoiceStateUpdateError::DataMissing(
+                "Member from new VoiceState missing".to_string(),
+            ))
+        }
+    };
+    let channel_id = match new.channel_id {
+        Some(x) => x,
+        None => {
+            return log_error!(&VoiceStateUpdateError::DataMissing(
+                "ChannelId from new VoiceState missing".to_string(),
+            ))
+        }
+    };
+
+    // Only a moderator can server mute or deafen, the audit log says who
+    let attribution = match change {
+        VoiceAction::ServerMute(_) | VoiceAction::ServerDeafen(_) => {
+            wait_for_member_action(ctx, guild_id, user.id, &[MemberAction::Update]).await
+        }
+        _ => None,
+    };
+
+    if let Err(e) = log_voice_state_changed(
+        user,
+        channel_id,
+        log_type,
+        title,
+        attribution.as_ref(),
+        guild_id,
+        ctx,
+    )
+    .await
+    {
+        log_error!(&e);
+    }
+}
+
+/// Record the change in the user's voice session, the finished session when they left
+async fn track_session(
+    ctx: &Context,
+    action: &VoiceAction,
+    data: &LogData,
+    guild_id: GuildId,
+) -> Option<VoiceSession> {
+    let store = match get_store(ctx).await {
+        Ok(store) => store,
+        Err(error) => {
+            log_error!(&error);
+            return None;
+        }
+    };
+
+    let user_id = data.0.id;
+    let tracked = match action {
+        VoiceAction::UserJoinedChannel => {
+            voice_sessions::joined(store.as_ref(), guild_id, user_id, data.1[0])
+                .await
+                .map(|_| None)
+        }
+        VoiceAction::UserMovedChannel => {
+            voice_sessions::moved(store.as_ref(), guild_id, user_id, data.1[0])
+                .await
+                .map(|_| None)
+        }
+        VoiceAction::UserLeftChannel => {
+            voice_sessions::left(store.as_ref(), guild_id, user_id).await
+        }
+        _ => Ok(None),
+    };
+
+    match tracked {
+        Ok(session) => session,
+        Err(error) => {
+            log_error!(&error);
+            None
+        }
+    }
+}
+
+pub async fn send_log(
+    ctx: Context,
+    log_type: VoiceAction,
+    data: LogData,
+    session: Option<VoiceSession>,
+    guild_id: GuildId,
+) {
     match log_type {
         VoiceAction::UserJoinedChannel => {
-            match log_voice_chat_joined(data.0, data.1[0], &ctx).await {
+            match log_voice_chat_joined(data.0, data.1[0], guild_id, &ctx).await {
+                Ok(_) => {}
+                Err(e) => log_error!(&e),
+            }
+        }
+        VoiceAction::UserLeftChannel => {
+            match log_voice_chat_left(data.0, data.1[0], session.as_ref(), guild_id, &ctx).await {
                 Ok(_) => {}
-                Err(e) => log_error(&e),
+                Err(e) => log_error!(&e),
             }
         }
-        VoiceAction::UserLeftChannel => match log_voice_chat_left(data.0, data.1[0], &ctx).await {
-            Ok(_) => {}
-            Err(e) => log_error(&e),
-        },
         VoiceAction::UserMovedChannel => {
-            match log_voice_chat_moved(data.0, data.1[1], data.1[0], &ctx).await {
+            match log_voice_chat_moved(data.0, data.1[1], data.1[0], guild_id, &ctx).await {
                 Ok(_) => {}
-                Err(e) => log_error(&e),
+                Err(e) => log_error!(&e),
             }
         }
-        _ => println!("Voice update happened..."),
+        _ => {}
     }
 }
 
 impl VoiceAction {
+    fn event_name(&self) -> &'static str {
+        match self {
+            Self::UserJoinedChannel => "voice_joined",
+            Self::UserLeftChannel => "voice_left",
+            Self::UserMovedChannel => "voice_moved",
+            Self::ServerMute(_) => "voice_server_mute",
+            Self::ServerDeafen(_) => "voice_server_deafen",
+            Self::SelfMute(_) => "voice_self_mute",
+            Self::SelfDeafen(_) => "voice_self_deafen",
+            Self::Stream(_) => "voice_stream",
+            Self::Video(_) => "voice_video",
+            Self::Unknown => "voice_other",
+        }
+    }
+
+    /// The log type and embed title of a change within a channel
+    fn describe(&self) -> Option<(LogType, &'static str)> {
+        let described = match self {
+            Self::ServerMute(true) => (LogType::VoiceServerMute, "Server Muted"),
+            Self::ServerMute(false) => (LogType::VoiceServerMute, "Server Unmuted"),
+            Self::ServerDeafen(true) => (LogType::VoiceServerMute, "Server Deafened"),
+            Self::ServerDeafen(false) => (LogType::VoiceServerMute, "Server Undeafened"),
+            Self::SelfMute(true) => (LogType::VoiceSelfMute, "Muted"),
+            Self::SelfMute(false) => (LogType::VoiceSelfMute, "Unmuted"),
+            Self::SelfDeafen(true) => (LogType::VoiceSelfMute, "Deafened"),
+            Self::SelfDeafen(false) => (LogType::VoiceSelfMute, "Undeafened"),
+            Self::Stream(true) => (LogType::VoiceStream, "Started Streaming"),
+            Self::Stream(false) => (LogType::VoiceStream, "Stopped Streaming"),
+            Self::Video(true) => (LogType::VoiceStream, "Turned Camera On"),
+            Self::Video(false) => (LogType::VoiceStream, "Turned Camera Off"),
+            _ => return None,
+        };
+        Some(described)
+    }
+
+    /// Everything that changed between `old` and `new` without switching channels
+    fn changes(old: &VoiceState, new: &VoiceState) -> Vec<Self> {
+        let mut changes = Vec::new();
+
+        if old.deaf != new.deaf {
+            changes.push(Self::ServerDeafen(new.deaf));
+        }
+        if old.mute != new.mute {
+            changes.push(Self::ServerMute(new.mute));
+        }
+        // Deafening yourself mutes you too, that mute is part of the deafen
+        if old.self_deaf != new.self_deaf {
+            changes.push(Self::SelfDeafen(new.self_deaf));
+        } else if old.self_mute != new.self_mute {
+            changes.push(Self::SelfMute(new.self_mute));
+        }
+        let (old_stream, new_stream) = (
+            old.self_stream.unwrap_or(false),
+            new.self_stream.unwrap_or(false),
+        );
+        if old_stream != new_stream {
+            changes.push(Self::Stream(new_stream));
+        }
+        if old.self_video != new.self_video {
+            changes.push(Self::Video(new.self_video));
+        }
+
+        changes
+    }
+
     fn new(old: &Option<VoiceState>, new: &VoiceState) -> Self {
-        let old_has_channel_id = old.is_some();
+        // The cache keeps the state of members who left, without a channel
+        let old_has_channel_id = old
+            .as_ref()
+            .and_then(|voice_state| voice_state.channel_id)
+            .is_some();
         let new_has_channel_id = new.channel_id.is_some();
 
         if old_has_channel_id && new_has_channel_id {
@@ -176,3 +445,113 @@ impl VoiceAction {
         Ok((user, vec![new_channel, old_channel]))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn in_voice() -> VoiceState {
+        serde_json::from_value(json!({
+            "channel_id": "20",
+            "deaf": false,
+            "guild_id": "1",
+            "mute": false,
+            "self_deaf": false,
+            "self_mute": false,
+            "self_stream": null,
+            "self_video": false,
+            "session_id": "session",
+            "suppress": false,
+            "user_id": "10",
+        }))
+        .unwrap()
+    }
+
+    fn changes(change: impl FnOnce(&mut VoiceState)) -> Vec<VoiceAction> {
+        let old = in_voice();
+        let mut new = old.clone();
+        change(&mut new);
+        VoiceAction::changes(&old, &new)
+    }
+
+    #[test]
+    fn server_mute_and_deafen_are_reported() {
+        assert_eq!(
+            changes(|state| state.mute = true),
+            vec![VoiceAction::ServerMute(true)]
+        );
+        assert_eq!(
+            changes(|state| state.deaf = true),
+            vec![VoiceAction::ServerDeafen(true)]
+        );
+    }
+
+    #[test]
+    fn self_deafen_includes_the_mute() {
+        assert_eq!(
+            changes(|state| state.self_mute = true),
+            vec![VoiceAction::SelfMute(true)]
+        );
+        assert_eq!(
+            changes(|state| {
+                state.self_deaf = true;
+                state.self_mute = true;
+            }),
+            vec![VoiceAction::SelfDeafen(true)]
+        );
+    }
+
+    #[test]
+    fn stream_and_video_toggles_are_reported() {
+        assert_eq!(
+            changes(|state| state.self_stream = Some(true)),
+            vec![VoiceAction::Stream(true)]
+        );
+        assert_eq!(
+            changes(|state| state.self_video = true),
+            vec![VoiceAction::Video(true)]
+        );
+
+        let mut old = in_voice();
+        old.self_stream = Some(true);
+        old.self_video = true;
+        let mut new = old.clone();
+        new.self_stream = Some(false);
+        new.self_video = false;
+        assert_eq!(
+            VoiceAction::changes(&old, &new),
+            vec![VoiceAction::Stream(false), VoiceAction::Video(false)]
+        );
+    }
+
+    #[test]
+    fn a_missing_stream_flag_is_not_streaming() {
+        assert!(changes(|state| state.self_stream = Some(false)).is_empty());
+    }
+
+    #[test]
+    fn combined_changes_are_all_reported() {
+        assert_eq!(
+            changes(|state| {
+                state.deaf = true;
+                state.mute = true;
+                state.self_mute = true;
+                state.self_stream = Some(true);
+                state.self_video = true;
+            }),
+            vec![
+                VoiceAction::ServerDeafen(true),
+                VoiceAction::ServerMute(true),
+                VoiceAction::SelfMute(true),
+                VoiceAction::Stream(true),
+                VoiceAction::Video(true),
+            ]
+        );
+    }
+
+    #[test]
+    fn nothing_changed_reports_nothing() {
+        assert!(changes(|_| ()).is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+use crate::log_channel::log_interaction_error;
+use crate::log_error;
+use crate::store::errors::StoreError;
+use serenity::client::Context;
+use serenity::model::id::GuildId;
+use serenity::model::user::User;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::error;
+
+type InteractionName = String;
+type SettingName = String;
+type FeatureName = String;
+type ErrorMessage = String;
+
+/// Error returned by slash commands, message components and modals
+#[derive(Error, Debug)]
+pub enum InteractionError {
+    #[error("{0}: Expected option(s)")]
+    ArgumentMissing(InteractionName),
+
+    #[error("{0}: {1}")]
+    UnresolvedData(InteractionName, ErrorMessage),
+
+    #[error("Redis: {0}")]
+    RedisError(ErrorMessage),
+
+    #[error("{0} is not configured, an admin can set it with `/config set`")]
+    NotConfigured(SettingName),
+
+    #[error("Cache: {0}")]
+    CacheError(ErrorMessage),
+
+    #[error("{0} are disabled, an admin can check `/bot-status`")]
+    Disabled(FeatureName),
+
+    /// The user asked for something the bot won't do, the message is shown as is
+    #[error("{0}")]
+    Rejected(ErrorMessage),
+
+    #[error("Error: `{0}`")]
+    Other(ErrorMessage),
+}
+
+impl InteractionError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            InteractionError::ArgumentMissing(_) => "argument_missing",
+            InteractionError::UnresolvedData(_, _) => "unresolved_data",
+            InteractionError::RedisError(_) => "redis",
+            InteractionError::NotConfigured(_) => "not_configured",
+            InteractionError::CacheError(_) => "cache",
+            InteractionError::Disabled(_) => "disabled",
+            InteractionError::Rejected(_) => "rejected",
+            InteractionError::Other(_) => "other",
+        }
+    }
+
+    /// Errors caused by the bot rather than the user, the user only gets a reference to these
+    pub fn is_internal(&self) -> bool {
+        !matches!(
+            self,
+            InteractionError::NotConfigured(_)
+                | InteractionError::Disabled(_)
+                | InteractionError::Rejected(_)
+        )
+    }
+}
+
+impl From<StoreError> for InteractionError {
+    fn from(error: StoreError) -> Self {
+        InteractionError::RedisError(error.to_string())
+    }
+}
+
+/// Who used which interaction where, sent along with internal errors
+pub struct ErrorContext {
+    /// e.g. "/config" or "component `pick-games-add`"
+    pub interaction: String,
+    pub caller: User,
+    pub guild_id: Option<GuildId>,
+    /// The options, selected values or modal inputs as `(name, value)`
+    pub options: Vec<(String, String)>,
+}
+
+static REFERENCE_COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// A short id like `A7F3` to find an error in the logs
+pub fn error_reference() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.subsec_nanos())
+        .unwrap_or_default();
+    let count = REFERENCE_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+    format!("{:04X}", (nanos ^ count.wrapping_mul(0x9E37)) & 0xFFFF)
+}
+
+/// Log `error` and work out what to tell the user.
+///
+/// Internal errors get a reference, the full error and `context` are posted to the
+/// developer error channel under it so the user never sees the details
+pub async fn report_error(
+    ctx: &Context,
+    error: &InteractionError,
+    context: &ErrorContext,
+) -> String {
+    if !error.is_internal() {
+        return error.to_string();
+    }
+
+    let reference = error_reference();
+    error!(reference = %reference, interaction = %context.interaction, "{}", error);
+
+    if let Err(error) = log_interaction_error(&reference, error, context, ctx).await {
+        log_error!(&error);
+    }
+
+    format!("Something went wrong, ref `{}`", reference)
+}
@@ -1,7 +1,18 @@
+pub mod api;
 pub mod application_commands;
+pub mod audit_log;
 pub mod events;
+pub mod interaction_error;
 pub mod log_channel;
 pub mod message_component_commands;
+pub mod metrics;
 pub mod modal_submits;
+pub mod permissions;
+pub mod readiness;
 pub mod redis_client;
+pub mod sharding;
+pub mod shutdown;
+pub mod store;
+pub mod temp_voice;
 pub mod utils;
+pub mod voice_sessions;
@@ -1,15 +1,30 @@
-use crate::redis_client;
+use crate::audit_log::Attribution;
+use crate::interaction_error::{ErrorContext, InteractionError};
+use crate::permissions::PermissionLevel;
+use crate::readiness::{CheckStatus, GuildReport};
+use crate::store::errors::StoreError;
 use crate::utils::discord_cdn::get_avatar_url;
 use crate::utils::time::date_diff;
-use chrono::Utc;
-use redis::Connection;
-use serenity::builder::{CreateEmbedAuthor, CreateEmbedFooter};
+use crate::voice_sessions::{format_duration, VoiceSession};
+use routing::resolve_route;
+use serde::{Deserialize, Serialize};
 use serenity::client::Context;
-use serenity::model::id::{ChannelId, UserId};
+use serenity::model::channel::Message;
+use serenity::model::id::{ChannelId, GuildId, MessageId, UserId};
 use serenity::model::user::User;
+use serenity::model::Timestamp;
+use std::env;
 
 use thiserror::Error;
 
+pub mod digest;
+pub mod entry;
+pub mod queue;
+pub mod routing;
+
+pub use entry::{LogEntry, Severity};
+pub use routing::LogType;
+
 type ErrorMessage = String;
 
 #[derive(Error, Debug)]
@@ -17,108 +32,152 @@ pub enum LogChannelError {
     #[error("Redis: {0}")]
     RedisError(ErrorMessage),
 
+    #[error("{0} is not configured, an admin can set it with `/config set`")]
+    NotConfigured(ErrorMessage),
+
+    #[error("{0} is disabled, see `/bot-status`")]
+    Disabled(ErrorMessage),
+
     #[error("Error: `{0}`")]
     Other(ErrorMessage),
 }
 
-const GREEN: i32 = 0x50C878;
-const RED: i32 = 0xFF0000;
-const YELLOW: i32 = 0xFFFF00;
+impl From<StoreError> for LogChannelError {
+    fn from(error: StoreError) -> Self {
+        LogChannelError::RedisError(error.to_string())
+    }
+}
+
+/// Where a `LogEntry` is posted
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum LogTarget {
+    /// Whichever channels the guild routed the log type to
+    Guild(GuildId, LogType),
+    /// The developer channel from `ERROR_CHANNEL_ID`, shared by every guild
+    Errors,
+}
+
+/// Queue `entry` for every channel behind `target`.
+///
+/// Routing problems are returned straight away, posting happens in the background
+/// and is retried until Discord takes it, see `queue`. Log types with a digest
+/// window are held back and posted together, see `digest`
+pub async fn send_log(
+    ctx: &Context,
+    target: LogTarget,
+    entry: LogEntry,
+) -> Result<(), LogChannelError> {
+    queue::start(ctx);
+
+    match target {
+        // A Redis outage is waited out by the queue like a Discord one
+        LogTarget::Guild(guild_id, log_type) => {
+            match resolve_route(ctx, guild_id, log_type).await {
+                Ok(channel_ids) if channel_ids.is_empty() => return Ok(()),
+                Ok(channel_ids) => {
+                    if let Some(window) = digest::window(ctx, guild_id, log_type).await {
+                        digest::add(guild_id, log_type, channel_ids, window, entry);
+                        return Ok(());
+                    }
+                }
+                Err(LogChannelError::RedisError(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        // Errors only go to stdout when no developer channel is set up
+        LogTarget::Errors => {
+            if error_channel().is_none() {
+                return Ok(());
+            }
+        }
+    }
+
+    queue::push(target, entry).await;
+
+    Ok(())
+}
+
+fn error_channel() -> Option<ChannelId> {
+    env::var("ERROR_CHANNEL_ID")
+        .ok()
+        .and_then(|id| id.parse::<u64>().ok())
+        .map(ChannelId)
+}
 
 pub async fn log_voice_chat_moved(
     user: User,
     old_voice_chat_id: ChannelId,
     new_voice_chat_id: ChannelId,
+    guild_id: GuildId,
     ctx: &Context,
 ) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
-
-    let channel_id = unpack_minor_channel_id(&mut conn)?;
-
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(&user));
-                author.name(user.name.clone());
-
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
-
-                e.title("Moved Voice Chat")
-                    .color(YELLOW)
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field("Left", format!("<#{}>", old_voice_chat_id), true)
-                    .field("Joined", format!("<#{}>", new_voice_chat_id), true)
-                    .field(
-                        "Username",
-                        format!("<@{}> - {}#{}", user.id, user.name, user.discriminator),
-                        false,
-                    )
-                    .set_footer(footer)
-            })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+    let entry = LogEntry::new("voice_chat_moved", Severity::Notice, "Moved Voice Chat")
+        .field("Left", format!("<#{}>", old_voice_chat_id), true)
+        .field("Joined", format!("<#{}>", new_voice_chat_id), true)
+        .summary(format!("moved to <#{}>", new_voice_chat_id))
+        .subject(&user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::VoiceMove), entry).await
+}
+
+/// A mute, deafen, stream or camera change while staying in `voice_chat_id`
+pub async fn log_voice_state_changed(
+    user: User,
+    voice_chat_id: ChannelId,
+    log_type: LogType,
+    title: &str,
+    attribution: Option<&Attribution>,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("voice_state_changed", Severity::Notice, title)
+        .description(format!(
+            "<@{}> in voice channel <#{}>",
+            user.id, voice_chat_id
+        ))
+        .summary(format!("{} in <#{}>", title.to_lowercase(), voice_chat_id));
+    let entry = with_attribution(entry, "Changed By", attribution).subject(&user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, log_type), entry).await
 }
 
 pub async fn log_voice_chat_left(
     user: User,
     voice_chat_id: ChannelId,
+    session: Option<&VoiceSession>,
+    guild_id: GuildId,
     ctx: &Context,
 ) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
-
-    let channel_id = unpack_minor_channel_id(&mut conn)?;
-
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(&user));
-                author.name(user.name.clone());
-
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
-
-                e.title("Left Voice Chat")
-                    .color(RED)
-                    .description(format!(
-                        "<@{}> left voice channel  <#{}>",
-                        user.id, voice_chat_id
-                    ))
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field(
-                        "Username",
-                        format!("{}#{}", user.name, user.discriminator),
-                        true,
-                    )
-                    .set_footer(footer)
-            })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+    let mut entry = LogEntry::new("voice_chat_left", Severity::Negative, "Left Voice Chat")
+        .description(format!(
+            "<@{}> left voice channel  <#{}>",
+            user.id, voice_chat_id
+        ))
+        .subject(&user);
+
+    // No session when the join happened while the bot was offline
+    entry = match session {
+        Some(session) => {
+            let duration = format_duration(session.duration());
+            entry
+                .summary(format!("left <#{}> after {}", voice_chat_id, duration))
+                .field("Time in Voice", duration, true)
+                .field("Channels", session.channels.len().to_string(), true)
+        }
+        None => entry.summary(format!("left <#{}>", voice_chat_id)),
+    };
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::VoiceLeave), entry).await
 }
+
 pub async fn log_roles_updated(
     user: User,
     new_roles: Vec<String>,
     old_roles: Vec<String>,
+    attribution: Option<&Attribution>,
+    guild_id: GuildId,
     ctx: &Context,
 ) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
-
-    let channel_id = unpack_major_channel_id(&mut conn)?;
-
     let old_roles = match old_roles.len() {
         0 => "None".to_string(),
         _ => old_roles.join(" "),
@@ -129,286 +188,532 @@ pub async fn log_roles_updated(
         _ => new_roles.join(" "),
     };
 
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(&user));
-                author.name(user.name.clone());
-
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
-
-                e.title("Roles Updated")
-                    .color(YELLOW)
-                    .description("🔄 🔄 🔄")
-                    .field("New Roles: ", new_roles, false)
-                    .field("Removed Roles: ", old_roles, false)
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field(
-                        "Username",
-                        format!("<@{}> - {}#{}", user.id, user.name, user.discriminator),
-                        false,
-                    )
-                    .set_footer(footer)
-            })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+    let entry = LogEntry::new("roles_updated", Severity::Notice, "Roles Updated")
+        .description("🔄 🔄 🔄")
+        .field("New Roles: ", new_roles, false)
+        .field("Removed Roles: ", old_roles, false);
+    let entry = with_attribution(entry, "Changed By", attribution).subject(&user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::RoleChange), entry).await
 }
-pub async fn log_voice_chat_joined(
-    user: User,
-    voice_chat_id: ChannelId,
+
+pub async fn log_nickname_changed(
+    user: &User,
+    before: Option<String>,
+    after: Option<String>,
+    guild_id: GuildId,
     ctx: &Context,
 ) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
-
-    let channel_id = unpack_minor_channel_id(&mut conn)?;
-
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(&user));
-                author.name(user.name.clone());
-
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
-
-                e.title("Joined Voice Chat")
-                    .color(GREEN)
-                    .description(format!(
-                        "<@{}> joined voice channel  <#{}>",
-                        user.id, voice_chat_id
-                    ))
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field(
-                        "Username",
-                        format!("{}#{}", user.name, user.discriminator),
-                        true,
-                    )
-                    .set_footer(footer)
-            })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+    let entry = LogEntry::new("nickname_changed", Severity::Notice, "Nickname Changed")
+        .field("Before", before.unwrap_or_else(|| "None".to_string()), true)
+        .field("After", after.unwrap_or_else(|| "None".to_string()), true)
+        .subject(user);
+
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::NicknameChange),
+        entry,
+    )
+    .await
 }
 
-pub async fn log_user_unbanned(unbanned_user: &User, ctx: &Context) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
+/// `before` and `after` are avatar URLs, `None` when the member has no server avatar
+pub async fn log_server_avatar_changed(
+    user: &User,
+    before: Option<String>,
+    after: Option<String>,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let mut entry = LogEntry::new(
+        "server_avatar_changed",
+        Severity::Notice,
+        "Server Avatar Changed",
+    )
+    .field("Before", avatar_link(before.as_deref()), true)
+    .field("After", avatar_link(after.as_deref()), true)
+    .subject(user);
+
+    if let Some(after) = after {
+        entry = entry.image(after);
+    }
 
-    let channel_id = unpack_major_channel_id(&mut conn)?;
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::ServerAvatarChange),
+        entry,
+    )
+    .await
+}
 
-    let user = unbanned_user;
+/// `until` is when the timeout ends, `None` when it was lifted
+pub async fn log_timeout_changed(
+    user: &User,
+    until: Option<Timestamp>,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = match until {
+        Some(until) => LogEntry::new("timeout_changed", Severity::Negative, "Member Timed Out")
+            .field(
+                "Until",
+                format!("<t:{0}:F> (<t:{0}:R>)", until.unix_timestamp()),
+                true,
+            ),
+        None => LogEntry::new("timeout_changed", Severity::Positive, "Timeout Removed"),
+    }
+    .subject(user);
+
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::TimeoutChange),
+        entry,
+    )
+    .await
+}
 
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(user));
-                author.name(user.name.clone());
+/// A member update that can't be compared, the previous state was not cached.
+///
+/// Only shows what the current state says on its own, nothing is posted when it says nothing
+pub async fn log_member_state(
+    user: &User,
+    nickname: Option<String>,
+    timed_out_until: Option<Timestamp>,
+    pending: bool,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let mut entry = LogEntry::new("member_updated", Severity::Notice, "Member Updated");
+    let mut shown = false;
 
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
+    if let Some(nickname) = nickname {
+        entry = entry.field("Nickname", nickname, true);
+        shown = true;
+    }
+    if let Some(until) = timed_out_until {
+        entry = entry.field(
+            "Timed Out Until",
+            format!("<t:{0}:F> (<t:{0}:R>)", until.unix_timestamp()),
+            true,
+        );
+        shown = true;
+    }
+    if pending {
+        entry = entry.field("Screening", "Not passed yet", true);
+        shown = true;
+    }
 
-                let account_age = date_diff(&user.created_at());
+    if !shown {
+        return Ok(());
+    }
 
-                e.title("Member Unbanned")
-                    .color(GREEN)
-                    .description(format!(
-                        "<@{}> - {}#{}",
-                        user.id, user.name, user.discriminator
-                    ))
-                    .image("https://i.ibb.co/7nqVFKd/unbanned.png")
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field("Account Age", account_age, true)
-                    .set_footer(footer)
-            })
-        })
-        .await;
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::MemberUpdate),
+        entry.subject(user),
+    )
+    .await
+}
 
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+pub async fn log_member_verified(
+    user: &User,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("member_verified", Severity::Positive, "Member Verified")
+        .description("Passed membership screening")
+        .subject(user);
+
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::MemberVerified),
+        entry,
+    )
+    .await
 }
 
-pub async fn log_user_banned(banned_user: &User, ctx: &Context) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
+pub async fn log_username_changed(
+    before: &User,
+    after: &User,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("username_changed", Severity::Notice, "Username Changed")
+        .field(
+            "Before",
+            format!("{}#{}", before.name, before.discriminator),
+            true,
+        )
+        .field(
+            "After",
+            format!("{}#{}", after.name, after.discriminator),
+            true,
+        )
+        .subject(after);
+
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::UsernameChange),
+        entry,
+    )
+    .await
+}
 
-    let channel_id = unpack_major_channel_id(&mut conn)?;
+pub async fn log_avatar_changed(
+    before: &User,
+    after: &User,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let before_url = get_avatar_url(before);
+    let after_url = get_avatar_url(after);
 
-    let user = banned_user;
+    let mut entry = LogEntry::new("avatar_changed", Severity::Notice, "Avatar Changed")
+        .field("Before", avatar_link(Some(&before_url)), true)
+        .field("After", avatar_link(Some(&after_url)), true)
+        .subject(after);
 
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(user));
-                author.name(user.name.clone());
+    if !after_url.is_empty() {
+        entry = entry.image(after_url);
+    }
 
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::AvatarChange),
+        entry,
+    )
+    .await
+}
 
-                let account_age = date_diff(&user.created_at());
+// `get_avatar_url` returns an empty string for the default avatar
+fn avatar_link(url: Option<&str>) -> String {
+    match url {
+        Some(url) if !url.is_empty() => format!("[Link]({})", url),
+        _ => "None".to_string(),
+    }
+}
 
-                e.title("Member Banned")
-                    .color(RED)
-                    .description(format!(
-                        "<@{}> - {}#{}",
-                        user.id, user.name, user.discriminator
-                    ))
-                    .image("https://i.ibb.co/P4m8YSL/banned.png")
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field("Account Age", account_age, true)
-                    .set_footer(footer)
-            })
-        })
-        .await;
+pub async fn log_voice_chat_joined(
+    user: User,
+    voice_chat_id: ChannelId,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("voice_chat_joined", Severity::Positive, "Joined Voice Chat")
+        .description(format!(
+            "<@{}> joined voice channel  <#{}>",
+            user.id, voice_chat_id
+        ))
+        .summary(format!("joined <#{}>", voice_chat_id))
+        .subject(&user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::VoiceJoin), entry).await
+}
 
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+pub async fn log_user_unbanned(
+    unbanned_user: &User,
+    attribution: Option<&Attribution>,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("user_unbanned", Severity::Positive, "Member Unbanned")
+        .field("Account Age", date_diff(&unbanned_user.created_at()), true)
+        .image("https://i.ibb.co/7nqVFKd/unbanned.png");
+    let entry = with_attribution(entry, "Unbanned By", attribution).subject(unbanned_user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::MemberUnban), entry).await
 }
 
-pub async fn log_user_joined(user_id: &UserId, ctx: &Context) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
+pub async fn log_user_banned(
+    banned_user: &User,
+    attribution: Option<&Attribution>,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("user_banned", Severity::Negative, "Member Banned")
+        .field("Account Age", date_diff(&banned_user.created_at()), true)
+        .image("https://i.ibb.co/P4m8YSL/banned.png");
+    let entry = with_attribution(entry, "Banned By", attribution).subject(banned_user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::MemberBan), entry).await
+}
 
+pub async fn log_user_joined(
+    user_id: &UserId,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
     let user = match user_id.to_user(&ctx.http).await {
         Ok(x) => x,
         Err(e) => return Err(LogChannelError::Other(e.to_string())),
     };
 
-    let channel_id = unpack_major_channel_id(&mut conn)?;
-
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(&user));
-                author.name(user.name.clone());
-
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
-
-                let account_age = date_diff(&user.created_at());
-
-                e.title("Member Joined")
-                    .color(GREEN)
-                    .description(format!(
-                        "<@{}> - {}#{}",
-                        user.id, user.name, user.discriminator
-                    ))
-                    .image(get_avatar_url(&user))
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field("Account Age", account_age, true)
-                    .set_footer(footer)
-            })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+    let entry = LogEntry::new("user_joined", Severity::Positive, "Member Joined")
+        .field("Account Age", date_diff(&user.created_at()), true)
+        .image(get_avatar_url(&user))
+        .subject(&user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::MemberJoin), entry).await
 }
 
-pub async fn log_user_left(user: &User, ctx: &Context) -> Result<(), LogChannelError> {
-    let mut conn = redis_client::connect();
-
-    let channel_id = unpack_major_channel_id(&mut conn)?;
-
-    let success = channel_id
-        .send_message(&ctx.http, |m| {
-            m.embed(|e| {
-                let mut author = CreateEmbedAuthor::default();
-                author.icon_url(get_avatar_url(user));
-                author.name(user.name.clone());
-
-                let mut footer = CreateEmbedFooter::default();
-                footer.text(format!("ID: {}", user.id));
-
-                let account_age = date_diff(&user.created_at());
-
-                e.title("Member Left")
-                    .color(RED)
-                    .description(format!(
-                        "<@{}> - {}#{}",
-                        user.id, user.name, user.discriminator
-                    ))
-                    .image("https://i.ibb.co/1qyVmzG/left-discord.png")
-                    .timestamp(Utc::now())
-                    .set_author(author)
-                    .field("Account Age", account_age, true)
-                    .set_footer(footer)
-            })
-        })
-        .await;
-
-    match success {
-        Ok(_) => Ok(()),
-        Err(e) => Err(LogChannelError::Other(e.to_string())),
-    }
+pub async fn log_user_left(
+    user: &User,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("user_left", Severity::Negative, "Member Left")
+        .field("Account Age", date_diff(&user.created_at()), true)
+        .image("https://i.ibb.co/1qyVmzG/left-discord.png")
+        .subject(user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::MemberLeave), entry).await
 }
 
-fn unpack_major_channel_id(conn: &mut Connection) -> Result<ChannelId, LogChannelError> {
-    // Query and unpack the log channel id from Redis
-    let channel_id = match redis_client::get_major_log_channel(conn) {
-        Ok(value) => match value {
-            Some(value) => match value.parse::<u64>() {
-                Ok(value) => value,
-                Err(_) => {
-                    return Err(LogChannelError::Other(
-                        "Could not parse major log channel id into u64".to_string(),
-                    ))
-                }
-            },
-            None => {
-                return Err(LogChannelError::RedisError(
-                    "Could not resolve major log channel id".to_string(),
-                ))
+pub async fn log_user_kicked(
+    user: &User,
+    attribution: &Attribution,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("user_kicked", Severity::Negative, "Member Kicked")
+        .field("Account Age", date_diff(&user.created_at()), true)
+        .image("https://i.ibb.co/1qyVmzG/left-discord.png");
+    let entry = with_attribution(entry, "Kicked By", Some(attribution)).subject(user);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::MemberKick), entry).await
+}
+
+// Show who made the change and why, when the audit log had a matching entry
+fn with_attribution(entry: LogEntry, label: &str, attribution: Option<&Attribution>) -> LogEntry {
+    let attribution = match attribution {
+        Some(x) => x,
+        None => return entry,
+    };
+
+    let moderator = match attribution.by_bot {
+        true => format!("<@{}> (this bot)", attribution.moderator),
+        false => format!("<@{}>", attribution.moderator),
+    };
+    let reason = attribution
+        .reason
+        .clone()
+        .unwrap_or_else(|| "No reason given".to_string());
+
+    entry
+        .field(label, moderator, true)
+        .field("Reason", reason, true)
+}
+
+pub async fn log_permission_denied(
+    user: &User,
+    command_name: &str,
+    required_level: PermissionLevel,
+    caller_level: PermissionLevel,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("permission_denied", Severity::Negative, "Command Denied")
+        .field("Command", format!("`/{}`", command_name), true)
+        .field("Required", required_level.label(), true)
+        .field("Has", caller_level.label(), true)
+        .subject(user);
+
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::CommandDenied),
+        entry,
+    )
+    .await
+}
+
+pub async fn log_message_edited(
+    author: &User,
+    before: Option<String>,
+    after: &str,
+    link: String,
+    channel_id: ChannelId,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let before = match before {
+        Some(before) => message_content(&before),
+        None => "Not cached".to_string(),
+    };
+
+    let entry = LogEntry::new("message_edited", Severity::Notice, "Message Edited")
+        .description(format!("[Jump to message]({}) in <#{}>", link, channel_id))
+        .field("Before", before, false)
+        .field("After", message_content(after), false)
+        .subject(author);
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::MessageEdit), entry).await
+}
+
+pub async fn log_message_deleted(
+    channel_id: ChannelId,
+    message_id: MessageId,
+    message: Option<&Message>,
+    pruned_by: Option<UserId>,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let mut entry = LogEntry::new("message_deleted", Severity::Negative, "Message Deleted")
+        .field("Channel", format!("<#{}>", channel_id), true)
+        .field("Message ID", message_id.to_string(), true);
+
+    if let Some(user_id) = pruned_by {
+        entry = entry.field("Deleted By", format!("<@{}> with `/prune`", user_id), true);
+    }
+
+    entry = match message {
+        Some(message) => {
+            entry = entry.field("Content", message_content(&message.content), false);
+            if !message.attachments.is_empty() {
+                let attachments: Vec<String> = message
+                    .attachments
+                    .iter()
+                    .map(|attachment| format!("[{}]({})", attachment.filename, attachment.url))
+                    .collect();
+                entry = entry.field("Attachments", attachments.join("\n"), false);
             }
-        },
-        Err(e) => return Err(LogChannelError::Other(e.to_string())),
+            entry.subject(&message.author)
+        }
+        None => entry.description("The message was not cached, its content and author are unknown"),
     };
 
-    Ok(ChannelId(channel_id))
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::MessageDelete),
+        entry,
+    )
+    .await
 }
 
-fn unpack_minor_channel_id(conn: &mut Connection) -> Result<ChannelId, LogChannelError> {
-    // Query and unpack the log channel id from Redis
-    let channel_id = match redis_client::get_minor_log_channel(conn) {
-        Ok(value) => match value {
-            Some(value) => match value.parse::<u64>() {
-                Ok(value) => value,
-                Err(_) => {
-                    return Err(LogChannelError::Other(
-                        "Could not parse minor log channel id into u64".to_string(),
-                    ))
+/// `messages` holds the deleted messages that were still cached
+pub async fn log_messages_bulk_deleted(
+    channel_id: ChannelId,
+    message_ids: &[MessageId],
+    messages: &[Message],
+    pruned_by: Option<UserId>,
+    guild_id: GuildId,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    // Ids grow over time, so sorting them puts the transcript in posting order
+    let mut message_ids = message_ids.to_vec();
+    message_ids.sort();
+
+    let mut transcript = String::new();
+    for message_id in &message_ids {
+        match messages.iter().find(|message| message.id == *message_id) {
+            Some(message) => {
+                transcript.push_str(&format!(
+                    "[{}] {}#{} ({}): {}\n",
+                    message.timestamp,
+                    message.author.name,
+                    message.author.discriminator,
+                    message.author.id,
+                    message.content
+                ));
+                for attachment in &message.attachments {
+                    transcript.push_str(&format!("    Attachment: {}\n", attachment.url));
                 }
-            },
-            None => {
-                return Err(LogChannelError::RedisError(
-                    "Could not resolve minor log channel id".to_string(),
-                ))
             }
-        },
-        Err(e) => return Err(LogChannelError::Other(e.to_string())),
+            None => transcript.push_str(&format!("[{}] Not cached\n", message_id)),
+        }
+    }
+
+    let mut entry = LogEntry::new(
+        "messages_bulk_deleted",
+        Severity::Negative,
+        "Messages Bulk Deleted",
+    )
+    .field("Channel", format!("<#{}>", channel_id), true)
+    .field("Deleted", message_ids.len().to_string(), true)
+    .field("Cached", messages.len().to_string(), true)
+    .file(format!("deleted-messages-{}.txt", channel_id), transcript);
+
+    if let Some(user_id) = pruned_by {
+        entry = entry.field("Deleted By", format!("<@{}> with `/prune`", user_id), true);
+    }
+
+    send_log(
+        ctx,
+        LogTarget::Guild(guild_id, LogType::MessageBulkDelete),
+        entry,
+    )
+    .await
+}
+
+// Discord rejects empty field values, messages with only attachments have no content
+fn message_content(content: &str) -> String {
+    match content.is_empty() {
+        true => "*No text*".to_string(),
+        false => content.to_string(),
+    }
+}
+
+pub async fn log_readiness_report(
+    report: &GuildReport,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let severity = match report.status() {
+        CheckStatus::Passed => Severity::Positive,
+        CheckStatus::Warning => Severity::Notice,
+        CheckStatus::Failed => Severity::Negative,
+    };
+
+    let entry =
+        LogEntry::new("readiness_report", severity, "Bot Started").description(report.render());
+
+    send_log(
+        ctx,
+        LogTarget::Guild(report.guild_id, LogType::BotStarted),
+        entry,
+    )
+    .await
+}
+
+pub async fn log_bot_offline(guild_id: GuildId, ctx: &Context) -> Result<(), LogChannelError> {
+    let entry = LogEntry::new("bot_offline", Severity::Notice, "Bot Going Offline")
+        .description("Logs and commands are paused until the bot is back");
+
+    send_log(ctx, LogTarget::Guild(guild_id, LogType::BotOffline), entry).await
+}
+
+pub async fn log_interaction_error(
+    reference: &str,
+    error: &InteractionError,
+    context: &ErrorContext,
+    ctx: &Context,
+) -> Result<(), LogChannelError> {
+    let guild = match context.guild_id {
+        Some(guild_id) => guild_id.to_string(),
+        None => "None".to_string(),
+    };
+
+    let options = match context.options.len() {
+        0 => "None".to_string(),
+        _ => {
+            let options: Vec<String> = context
+                .options
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect();
+            format!("```{}```", entry::truncate(&options.join("\n"), 900))
+        }
     };
 
-    Ok(ChannelId(channel_id))
+    let entry = LogEntry::new("interaction_error", Severity::Negative, "Interaction Error")
+        .description(format!(
+            "```{}```",
+            entry::truncate(&error.to_string(), 3900)
+        ))
+        .field("Kind", error.kind(), true)
+        .field("Interaction", &context.interaction, true)
+        .field("Guild", guild, true)
+        .field("Ref", reference, true)
+        .field("Options", options, false)
+        .subject(&context.caller);
+
+    send_log(ctx, LogTarget::Errors, entry).await
 }
@@ -0,0 +1,170 @@
+use serenity::client::Context;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use serenity::model::user::User;
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+
+use super::{queue, LogEntry, LogTarget, LogType, Severity};
+use crate::store::get_store;
+
+/// Longest digest window `/log-routes digest` accepts
+pub const MAX_WINDOW_MINUTES: i64 = 60;
+
+// Logs routed to the same channels share a digest, so a join, move and leave end up on one line
+type BatchKey = (GuildId, Vec<ChannelId>);
+
+static BATCHES: OnceLock<Mutex<HashMap<BatchKey, Vec<(LogType, LogEntry)>>>> = OnceLock::new();
+
+// Digest windows by log type name per guild, so logging doesn't read the store every time.
+// A guild's events and its `/log-routes` commands reach the same shard, this process
+static WINDOWS: OnceLock<Mutex<HashMap<GuildId, HashMap<String, String>>>> = OnceLock::new();
+
+fn batches() -> MutexGuard<'static, HashMap<BatchKey, Vec<(LogType, LogEntry)>>> {
+    BATCHES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+fn windows() -> MutexGuard<'static, HashMap<GuildId, HashMap<String, String>>> {
+    WINDOWS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The digest window of `log_type`, `None` when every event is posted on its own
+pub async fn window(ctx: &Context, guild_id: GuildId, log_type: LogType) -> Option<Duration> {
+    let cached = windows()
+        .get(&guild_id)
+        .map(|digests| digests.get(log_type.name()).cloned());
+
+    let minutes = match cached {
+        Some(minutes) => minutes,
+        None => {
+            let store = get_store(ctx).await.ok()?;
+            let digests = store.list_log_digests(guild_id).await.ok()?;
+            let minutes = digests.get(log_type.name()).cloned();
+            windows().insert(guild_id, digests);
+            minutes
+        }
+    };
+
+    minutes
+        .and_then(|minutes| minutes.parse::<u64>().ok())
+        .filter(|minutes| *minutes > 0)
+        .map(|minutes| Duration::from_secs(minutes * 60))
+}
+
+/// Drop the cached windows of `guild_id` after they were changed
+pub fn invalidate(guild_id: GuildId) {
+    windows().remove(&guild_id);
+}
+
+/// Hold `entry` back for the digest of `channel_ids`, the first entry of a window schedules the post.
+///
+/// Held entries only live in memory until the window ends
+pub fn add(
+    guild_id: GuildId,
+    log_type: LogType,
+    mut channel_ids: Vec<ChannelId>,
+    window: Duration,
+    entry: LogEntry,
+) {
+    channel_ids.sort();
+    let key = (guild_id, channel_ids);
+
+    let first = {
+        let mut batches = batches();
+        let batch = batches.entry(key.clone()).or_default();
+        batch.push((log_type, entry));
+        batch.len() == 1
+    };
+
+    if first {
+        tokio::spawn(async move {
+            tokio::time::sleep(window).await;
+            post(key).await;
+        });
+    }
+}
+
+/// Queue every digest that is still collecting, so a shutdown doesn't lose them
+pub async fn flush_all() {
+    let keys: Vec<BatchKey> = batches().keys().cloned().collect();
+
+    for key in keys {
+        post(key).await;
+    }
+}
+
+async fn post(key: BatchKey) {
+    let batch = batches().remove(&key);
+    let batch = match batch {
+        Some(batch) if !batch.is_empty() => batch,
+        _ => return,
+    };
+
+    // Every entry in the batch is routed to the same channels, any of their types reaches them
+    let log_type = batch[0].0;
+    queue::push(LogTarget::Guild(key.0, log_type), digest(batch)).await;
+}
+
+// One line per user in the order they first show up, e.g. "Alice: joined #Lobby, moved to #Raid"
+fn digest(batch: Vec<(LogType, LogEntry)>) -> LogEntry {
+    let mut labels: Vec<&'static str> = Vec::new();
+    let mut lines: Vec<(Option<User>, Vec<String>)> = Vec::new();
+    let mut users: HashMap<UserId, usize> = HashMap::new();
+
+    let first = batch[0].1.timestamp;
+    let last = batch[batch.len() - 1].1.timestamp;
+    let count = batch.len();
+
+    for (log_type, entry) in batch {
+        if !labels.contains(&log_type.label()) {
+            labels.push(log_type.label());
+        }
+
+        let summary = entry.summary.unwrap_or(entry.title);
+        match entry.subject {
+            Some(user) => match users.get(&user.id) {
+                Some(index) => lines[*index].1.push(summary),
+                None => {
+                    users.insert(user.id, lines.len());
+                    lines.push((Some(user), vec![summary]));
+                }
+            },
+            None => lines.push((None, vec![summary])),
+        }
+    }
+
+    let description: Vec<String> = lines
+        .iter()
+        .map(|(user, summaries)| match user {
+            Some(user) => format!("**{}** <@{}>: {}", user.name, user.id, summaries.join(", ")),
+            None => summaries.join(", "),
+        })
+        .collect();
+    let description = description.join("\n");
+
+    let mut entry = LogEntry::new("log_digest", Severity::Notice, "Log Digest")
+        .field("Types", labels.join(", "), false)
+        .field("Events", count.to_string(), true)
+        .field(
+            "Between",
+            format!(
+                "<t:{}:t> and <t:{}:t>",
+                first.unix_timestamp(),
+                last.unix_timestamp()
+            ),
+            true,
+        );
+
+    // The embed cuts the description short, the full digest goes along as a file
+    if description.chars().count() > 4000 {
+        entry = entry.file("digest.txt", description.clone());
+    }
+
+    entry.description(description)
+}
@@ -0,0 +1,239 @@
+use crate::utils::discord_cdn::get_avatar_url;
+use serde::{Deserialize, Serialize};
+use serenity::builder::CreateEmbed;
+use serenity::model::user::User;
+use serenity::model::Timestamp;
+
+const GREEN: i32 = 0x50C878;
+const RED: i32 = 0xFF0000;
+const YELLOW: i32 = 0xFFFF00;
+
+/// How good or bad the logged event is, picks the embed color
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Severity {
+    Positive,
+    Notice,
+    Negative,
+}
+
+impl Severity {
+    pub fn color(&self) -> i32 {
+        match self {
+            Severity::Positive => GREEN,
+            Severity::Notice => YELLOW,
+            Severity::Negative => RED,
+        }
+    }
+}
+
+/// One log channel post, rendered the same way for every kind of log.
+///
+/// Entries are serialized into the log queue, so they can be delivered after a restart
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LogEntry {
+    /// Short name used in metrics and error messages, e.g. "user_banned"
+    pub name: String,
+    /// When the logged event happened, delivery can be much later
+    pub timestamp: Timestamp,
+    pub severity: Severity,
+    pub title: String,
+    /// Short line used when the entry is folded into a digest, e.g. "joined <#1>"
+    #[serde(default)]
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub fields: Vec<(String, String, bool)>,
+    /// Who the entry is about, shown as the author, footer and "Username" field
+    pub subject: Option<User>,
+    pub image: Option<String>,
+    /// Overrides the severity color
+    pub color: Option<i32>,
+    /// Uploaded next to the embed as `(filename, contents)`, for content too long for a field
+    #[serde(default, with = "file_base64")]
+    pub file: Option<(String, Vec<u8>)>,
+}
+
+// The queue journal stores files as base64, as a JSON number array they'd be several times larger
+mod file_base64 {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Contents {
+        Encoded(String),
+        // Journals written before files were encoded
+        Bytes(Vec<u8>),
+    }
+
+    pub fn serialize<S: Serializer>(
+        file: &Option<(String, Vec<u8>)>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        file.as_ref()
+            .map(|(filename, contents)| (filename, base64::encode(contents)))
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<(String, Vec<u8>)>, D::Error> {
+        let file = Option::<(String, Contents)>::deserialize(deserializer)?;
+        file.map(|(filename, contents)| match contents {
+            Contents::Encoded(encoded) => base64::decode(encoded)
+                .map(|contents| (filename, contents))
+                .map_err(D::Error::custom),
+            Contents::Bytes(contents) => Ok((filename, contents)),
+        })
+        .transpose()
+    }
+}
+
+impl LogEntry {
+    pub fn new(name: &'static str, severity: Severity, title: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            timestamp: Timestamp::now(),
+            severity,
+            title: title.into(),
+            summary: None,
+            description: None,
+            fields: Vec::new(),
+            subject: None,
+            image: None,
+            color: None,
+            file: None,
+        }
+    }
+
+    pub fn summary(mut self, summary: impl Into<String>) -> Self {
+        self.summary = Some(summary.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn field(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+        inline: bool,
+    ) -> Self {
+        self.fields.push((name.into(), value.into(), inline));
+        self
+    }
+
+    pub fn subject(mut self, user: &User) -> Self {
+        self.subject = Some(user.clone());
+        self
+    }
+
+    pub fn image(mut self, url: impl Into<String>) -> Self {
+        self.image = Some(url.into());
+        self
+    }
+
+    pub fn color(mut self, color: i32) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn file(mut self, filename: impl Into<String>, contents: impl Into<Vec<u8>>) -> Self {
+        self.file = Some((filename.into(), contents.into()));
+        self
+    }
+
+    pub fn render<'a>(&self, e: &'a mut CreateEmbed) -> &'a mut CreateEmbed {
+        e.title(&self.title)
+            .color(self.color.unwrap_or_else(|| self.severity.color()))
+            .timestamp(self.timestamp);
+
+        if let Some(description) = &self.description {
+            e.description(truncate(description, 4000));
+        }
+
+        for (name, value, inline) in &self.fields {
+            e.field(name, truncate(value, 1000), *inline);
+        }
+
+        if let Some(image) = &self.image {
+            e.image(image);
+        }
+
+        if let Some(user) = &self.subject {
+            e.author(|a| a.icon_url(get_avatar_url(user)).name(&user.name))
+                .field(
+                    "Username",
+                    format!("<@{}> - {}#{}", user.id, user.name, user.discriminator),
+                    false,
+                )
+                .footer(|f| f.text(format!("ID: {}", user.id)));
+        }
+
+        e
+    }
+}
+
+/// Embeds reject descriptions and fields over their length limit
+pub fn truncate(content: &str, max_chars: usize) -> String {
+    match content.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &content[..index]),
+        None => content.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::env;
+
+    fn with_file() -> LogEntry {
+        LogEntry::new("message_delete", Severity::Negative, "Message Deleted")
+            .file("message.txt", "hello")
+    }
+
+    #[test]
+    fn files_are_stored_as_base64() {
+        let entry = serde_json::to_value(with_file()).unwrap();
+        assert_eq!(entry["file"], json!(["message.txt", "aGVsbG8="]));
+    }
+
+    #[test]
+    fn files_survive_a_round_trip_through_a_file() {
+        let path = env::temp_dir().join(format!("mythicc_log_entry_{}.json", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&with_file()).unwrap()).unwrap();
+
+        let entry: LogEntry =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            entry.file,
+            Some(("message.txt".to_string(), b"hello".to_vec()))
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn byte_arrays_from_older_journals_are_read() {
+        let mut entry = serde_json::to_value(with_file()).unwrap();
+        entry["file"] = json!(["message.txt", [104, 101, 108, 108, 111]]);
+
+        let entry: LogEntry = serde_json::from_value(entry).unwrap();
+        assert_eq!(
+            entry.file,
+            Some(("message.txt".to_string(), b"hello".to_vec()))
+        );
+    }
+
+    #[test]
+    fn entries_without_a_file_are_read() {
+        let mut entry = serde_json::to_value(with_file()).unwrap();
+        entry.as_object_mut().unwrap().remove("file");
+
+        let entry: LogEntry = serde_json::from_value(entry).unwrap();
+        assert!(entry.file.is_none());
+    }
+}
@@ -0,0 +1,682 @@
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::http::error::Error as HttpError;
+use serenity::model::id::{ChannelId, GuildId};
+use serenity::model::Timestamp;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::env;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::Duration;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Notify;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+use super::routing::resolve_route;
+use super::{error_channel, LogChannelError, LogEntry, LogTarget, LogType, Severity};
+use crate::metrics::{record_discord_http_error, record_log_failure, record_log_queue_depth};
+
+const DEFAULT_QUEUE_PATH: &str = "log_queue.jsonl";
+// Oldest entries are dropped past this, so a long outage can't fill the disk
+const MAX_PENDING: usize = 5000;
+// A log this old is no use to the moderators anymore
+const MAX_AGE_SECS: i64 = 24 * 60 * 60;
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+static QUEUE: OnceLock<LogQueue> = OnceLock::new();
+
+#[derive(Clone, Serialize, Deserialize)]
+struct QueuedLog {
+    id: u64,
+    target: LogTarget,
+    entry: LogEntry,
+}
+
+/// One line of the journal, replayed on start up to find the logs that were never posted
+#[derive(Serialize, Deserialize)]
+enum Record {
+    Queued(QueuedLog),
+    Done(u64),
+}
+
+/// Logs a guild did not get on time, summarised once delivery works again
+#[derive(Default)]
+struct Missed {
+    delayed: usize,
+    dropped: usize,
+}
+
+#[derive(Default)]
+struct Pending {
+    logs: VecDeque<QueuedLog>,
+    /// When delivery started failing, `None` while logs are posted on time
+    outage_since: Option<Timestamp>,
+    missed: HashMap<GuildId, Missed>,
+}
+
+impl Pending {
+    fn start_outage(&mut self) {
+        if self.outage_since.is_none() {
+            self.outage_since = Some(Timestamp::now());
+        }
+    }
+
+    fn count(&mut self, log: &QueuedLog, delivered: bool) {
+        // A summary that could not be posted would only cause another summary
+        let guild_id = match log.target {
+            LogTarget::Guild(_, LogType::LogOutage) | LogTarget::Errors => return,
+            LogTarget::Guild(guild_id, _) => guild_id,
+        };
+
+        let missed = self.missed.entry(guild_id).or_default();
+        if delivered {
+            missed.delayed += 1;
+        } else {
+            missed.dropped += 1;
+        }
+    }
+}
+
+/// Log channel posts waiting to be delivered, journaled to a file so they survive a restart
+struct LogQueue {
+    /// `None` keeps the queue in memory only
+    path: Option<PathBuf>,
+    pending: Mutex<Pending>,
+    /// Held while writing the journal, so a compaction never races an append
+    journal: tokio::sync::Mutex<()>,
+    next_id: AtomicU64,
+    wake: Notify,
+    drained: Notify,
+    worker_started: AtomicBool,
+}
+
+/// Open the journal from `LOG_QUEUE_PATH` and load the logs a previous run did not deliver.
+///
+/// Call before the client starts, the worker picks the leftovers up on the first `ready`
+pub fn open() {
+    let path = match env::var("LOG_QUEUE_PATH") {
+        Ok(path) if path.is_empty() => None,
+        Ok(path) => Some(PathBuf::from(path)),
+        Err(_) => Some(PathBuf::from(DEFAULT_QUEUE_PATH)),
+    };
+
+    let mut pending = Pending::default();
+    if let Some(path) = &path {
+        replay(path, &mut pending);
+    }
+    if !pending.logs.is_empty() {
+        info!(
+            "{} log entries from the last run are waiting to be posted",
+            pending.logs.len()
+        );
+        pending.start_outage();
+    }
+
+    let next_id = pending.logs.iter().map(|log| log.id + 1).max().unwrap_or(0);
+    record_log_queue_depth(pending.logs.len());
+
+    let queue = LogQueue {
+        path,
+        pending: Mutex::new(pending),
+        journal: tokio::sync::Mutex::new(()),
+        next_id: AtomicU64::new(next_id),
+        wake: Notify::new(),
+        drained: Notify::new(),
+        worker_started: AtomicBool::new(false),
+    };
+
+    if QUEUE.set(queue).is_err() {
+        warn!("The log queue was already opened");
+    }
+}
+
+// Falls back to a memory only queue when `open` was never called
+fn queue() -> &'static LogQueue {
+    QUEUE.get_or_init(|| LogQueue {
+        path: None,
+        pending: Mutex::new(Pending::default()),
+        journal: tokio::sync::Mutex::new(()),
+        next_id: AtomicU64::new(0),
+        wake: Notify::new(),
+        drained: Notify::new(),
+        worker_started: AtomicBool::new(false),
+    })
+}
+
+// Seconds since the logged event happened
+fn age_secs(log: &QueuedLog) -> i64 {
+    Timestamp::now().unix_timestamp() - log.entry.timestamp.unix_timestamp()
+}
+
+// Load every queued log without a matching `Done` into `pending`, then rewrite the journal
+// with just those. The age and size limits apply like they do while running
+fn replay(path: &Path, pending: &mut Pending) {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return,
+        Err(error) => {
+            warn!("Could not read the log queue {}: {}", path.display(), error);
+            return;
+        }
+    };
+
+    let mut logs = BTreeMap::new();
+    for line in BufReader::new(file).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                warn!("Could not read the log queue {}: {}", path.display(), error);
+                break;
+            }
+        };
+
+        // The last line is cut short when the bot was killed mid write
+        match serde_json::from_str::<Record>(&line) {
+            Ok(Record::Queued(log)) => {
+                logs.insert(log.id, log);
+            }
+            Ok(Record::Done(id)) => {
+                logs.remove(&id);
+            }
+            Err(error) => debug!("Skipping a broken log queue line: {}", error),
+        }
+    }
+
+    let (mut dropped, mut logs): (Vec<QueuedLog>, VecDeque<QueuedLog>) = logs
+        .into_values()
+        .partition(|log| age_secs(log) > MAX_AGE_SECS);
+    while logs.len() > MAX_PENDING {
+        dropped.extend(logs.pop_front());
+    }
+    if !dropped.is_empty() {
+        warn!(
+            "Dropping {} log entries from the last run, they are too old or too many",
+            dropped.len()
+        );
+        pending.start_outage();
+        for log in &dropped {
+            pending.count(log, false);
+        }
+    }
+
+    let mut journal = String::new();
+    for log in &logs {
+        if let Ok(line) = serde_json::to_string(&Record::Queued(log.clone())) {
+            journal.push_str(&line);
+            journal.push('\n');
+        }
+    }
+    if let Err(error) = std::fs::write(path, journal) {
+        warn!(
+            "Could not compact the log queue {}: {}",
+            path.display(),
+            error
+        );
+    }
+
+    pending.logs = logs;
+}
+
+impl LogQueue {
+    fn pending(&self) -> MutexGuard<'_, Pending> {
+        // A panic mid-update leaves the queue usable, dropping logs over it would be worse
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending().logs.is_empty()
+    }
+
+    fn front(&self) -> Option<QueuedLog> {
+        self.pending().logs.front().cloned()
+    }
+
+    async fn append(&self, records: &[Record]) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        let mut lines = String::new();
+        for record in records {
+            match serde_json::to_string(record) {
+                Ok(line) => {
+                    lines.push_str(&line);
+                    lines.push('\n');
+                }
+                Err(error) => warn!("Could not write a log queue record: {}", error),
+            }
+        }
+
+        let written = async {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(lines.as_bytes()).await?;
+            file.flush().await
+        };
+
+        // The log is still posted from memory, it just won't survive a restart
+        if let Err(error) = written.await {
+            warn!(
+                "Could not write the log queue {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+
+    // Everything was delivered, start the journal over so it doesn't grow forever
+    async fn truncate(&self) {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return,
+        };
+
+        if let Err(error) = tokio::fs::write(path, "").await {
+            warn!(
+                "Could not compact the log queue {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+
+    async fn push(&self, target: LogTarget, entry: LogEntry) {
+        let log = QueuedLog {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            target,
+            entry,
+        };
+
+        let _journal = self.journal.lock().await;
+
+        let mut records = vec![Record::Queued(log.clone())];
+        let depth = {
+            let mut pending = self.pending();
+            pending.logs.push_back(log);
+            if pending.logs.len() > MAX_PENDING {
+                if let Some(oldest) = pending.logs.pop_front() {
+                    warn!("Log queue is full, dropping `{}`", oldest.entry.name);
+                    pending.start_outage();
+                    pending.count(&oldest, false);
+                    records.push(Record::Done(oldest.id));
+                }
+            }
+            pending.logs.len()
+        };
+
+        self.append(&records).await;
+        record_log_queue_depth(depth);
+        self.wake.notify_one();
+    }
+
+    // Take `id` off the queue once it was posted, or given up on when `delivered` is false
+    async fn complete(&self, id: u64, delivered: bool) {
+        let _journal = self.journal.lock().await;
+
+        let depth = {
+            let mut pending = self.pending();
+            if let Some(index) = pending.logs.iter().position(|log| log.id == id) {
+                if let Some(log) = pending.logs.remove(index) {
+                    if pending.outage_since.is_some() {
+                        pending.count(&log, delivered);
+                    }
+                }
+            }
+            pending.logs.len()
+        };
+
+        match depth {
+            0 => self.truncate().await,
+            _ => self.append(&[Record::Done(id)]).await,
+        }
+        record_log_queue_depth(depth);
+
+        if depth == 0 {
+            self.drained.notify_waiters();
+        }
+    }
+
+    fn start_outage(&self) {
+        self.pending().start_outage();
+    }
+
+    // Queue a "Logs Delayed" post for every guild that missed logs, once the outage is over
+    async fn summarize_outage(&self) {
+        let (since, missed) = {
+            let mut pending = self.pending();
+            match pending.outage_since.take() {
+                Some(since) => (since, std::mem::take(&mut pending.missed)),
+                None => return,
+            }
+        };
+
+        info!(
+            "Log delivery recovered, {} guilds missed logs since {}",
+            missed.len(),
+            since
+        );
+
+        for (guild_id, missed) in missed {
+            let entry = LogEntry::new("log_outage", Severity::Notice, "Logs Delayed")
+                .description(format!(
+                    "Log channels could not be reached for a while, {} log entries were posted late and {} were missed",
+                    missed.delayed, missed.dropped
+                ))
+                .field(
+                    "Since",
+                    format!("<t:{}:f>", since.unix_timestamp()),
+                    true,
+                )
+                .field("Delayed", missed.delayed.to_string(), true)
+                .field("Missed", missed.dropped.to_string(), true);
+
+            self.push(LogTarget::Guild(guild_id, LogType::LogOutage), entry)
+                .await;
+        }
+    }
+}
+
+/// Queue `entry` for the background worker
+pub async fn push(target: LogTarget, entry: LogEntry) {
+    queue().push(target, entry).await;
+}
+
+/// Start delivering queued logs, does nothing when the worker already runs
+pub fn start(ctx: &Context) {
+    if queue().worker_started.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    tokio::spawn(run(ctx.clone()));
+}
+
+/// Wait until every queued log is posted, `false` when some are left after `deadline`.
+///
+/// Leftovers stay in the journal and are posted after the next start
+pub async fn flush(deadline: Duration) -> bool {
+    let queue = queue();
+    let drained = async {
+        loop {
+            // Register before checking, so a log completing in between still wakes us
+            let drained = queue.drained.notified();
+            if queue.is_empty() {
+                return;
+            }
+            drained.await;
+        }
+    };
+
+    timeout(deadline, drained).await.is_ok()
+}
+
+/// Logs still waiting, for the shutdown warning
+pub fn pending() -> usize {
+    queue().pending().logs.len()
+}
+
+enum Delivery {
+    Sent,
+    /// Discord or Redis can't be reached right now, try the same log again later
+    Retry(String),
+    /// Posting this log can never work, e.g. the route points to a deleted channel
+    Drop(String),
+}
+
+// Posts one log at a time, so logs show up in the order they happened
+async fn run(ctx: Context) {
+    let queue = queue();
+    let mut backoff = MIN_BACKOFF;
+    // Channels that already got the log at the front, a retry only posts to the rest
+    let mut delivered: HashSet<ChannelId> = HashSet::new();
+    let mut current = None;
+
+    loop {
+        let log = match queue.front() {
+            Some(log) => log,
+            None => {
+                queue.summarize_outage().await;
+                if queue.is_empty() {
+                    queue.wake.notified().await;
+                }
+                continue;
+            }
+        };
+
+        if current != Some(log.id) {
+            current = Some(log.id);
+            delivered.clear();
+        }
+
+        match deliver(&ctx, &log, &mut delivered).await {
+            Delivery::Sent => {
+                queue.complete(log.id, true).await;
+                backoff = MIN_BACKOFF;
+            }
+            Delivery::Drop(why) => {
+                warn!("Dropping log `{}`: {}", log.entry.name, why);
+                record_log_failure(&log.entry.name);
+                queue.complete(log.id, false).await;
+            }
+            Delivery::Retry(why) => {
+                warn!(
+                    "Could not post log `{}`, retrying in {:?}: {}",
+                    log.entry.name, backoff, why
+                );
+                queue.start_outage();
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+async fn deliver(ctx: &Context, log: &QueuedLog, delivered: &mut HashSet<ChannelId>) -> Delivery {
+    let age = age_secs(log);
+    if age > MAX_AGE_SECS {
+        return Delivery::Drop(format!("still not posted after {} hours", age / 3600));
+    }
+
+    let channel_ids = match log.target {
+        LogTarget::Guild(guild_id, log_type) => {
+            match resolve_route(ctx, guild_id, log_type).await {
+                Ok(channel_ids) => channel_ids,
+                Err(LogChannelError::RedisError(why)) => return Delivery::Retry(why),
+                Err(error) => return Delivery::Drop(error.to_string()),
+            }
+        }
+        LogTarget::Errors => error_channel().into_iter().collect(),
+    };
+
+    let entry = &log.entry;
+    for channel_id in channel_ids {
+        if delivered.contains(&channel_id) {
+            continue;
+        }
+
+        // Serenity waits out the rate limit buckets it knows of before sending
+        let success = channel_id
+            .send_message(&ctx.http, |m| {
+                m.embed(|e| entry.render(e));
+                if let Some((filename, contents)) = &entry.file {
+                    m.add_file((contents.as_slice(), filename.as_str()));
+                }
+                m
+            })
+            .await;
+
+        match success {
+            Ok(_) => {
+                delivered.insert(channel_id);
+            }
+            Err(error) => {
+                record_discord_http_error("log_channel");
+                if is_transient(&error) {
+                    return Delivery::Retry(error.to_string());
+                }
+
+                // One broken channel shouldn't hold the log back from the others
+                warn!(
+                    "Could not post log `{}` to {}: {}",
+                    entry.name, channel_id, error
+                );
+                record_log_failure(&entry.name);
+                delivered.insert(channel_id);
+            }
+        }
+    }
+
+    Delivery::Sent
+}
+
+// Rate limits, Discord outages and network errors pass, a missing permission doesn't
+fn is_transient(error: &serenity::Error) -> bool {
+    match error {
+        serenity::Error::Http(error) => match error.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => {
+                response.status_code.as_u16() == 429 || response.status_code.is_server_error()
+            }
+            HttpError::Request(_) => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD: GuildId = GuildId(1);
+
+    // Every test gets a journal of its own, they run in parallel
+    fn journal(name: &str, records: &[Record]) -> PathBuf {
+        let path = env::temp_dir().join(format!(
+            "mythicc_log_queue_{}_{}.jsonl",
+            name,
+            std::process::id()
+        ));
+        let mut lines = String::new();
+        for record in records {
+            lines.push_str(&serde_json::to_string(record).unwrap());
+            lines.push('\n');
+        }
+        std::fs::write(&path, lines).unwrap();
+        path
+    }
+
+    fn queued_at(id: u64, timestamp: Timestamp) -> Record {
+        let mut entry = LogEntry::new("member_join", Severity::Positive, "Member Joined");
+        entry.timestamp = timestamp;
+        Record::Queued(QueuedLog {
+            id,
+            target: LogTarget::Guild(GUILD, LogType::MemberJoin),
+            entry,
+        })
+    }
+
+    fn queued(id: u64) -> Record {
+        queued_at(id, Timestamp::now())
+    }
+
+    fn replayed(path: &Path) -> Pending {
+        let mut pending = Pending::default();
+        replay(path, &mut pending);
+        pending
+    }
+
+    fn ids(pending: &Pending) -> Vec<u64> {
+        pending.logs.iter().map(|log| log.id).collect()
+    }
+
+    #[test]
+    fn done_logs_are_not_replayed() {
+        let path = journal(
+            "done",
+            &[
+                queued(0),
+                queued(1),
+                Record::Done(0),
+                queued(2),
+                Record::Done(2),
+            ],
+        );
+
+        let pending = replayed(&path);
+        assert_eq!(ids(&pending), vec![1]);
+        assert!(pending.outage_since.is_none());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn the_journal_is_compacted() {
+        let path = journal("compact", &[queued(0), queued(1), Record::Done(0)]);
+        replayed(&path);
+
+        let journal = std::fs::read_to_string(&path).unwrap();
+        let records: Vec<Record> = journal
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert!(matches!(records.as_slice(), [Record::Queued(log)] if log.id == 1));
+        assert_eq!(ids(&replayed(&path)), vec![1]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_truncated_last_line_is_skipped() {
+        let path = journal("truncated", &[queued(0), queued(1)]);
+        let line = serde_json::to_string(&queued(2)).unwrap();
+        let mut journal = std::fs::read_to_string(&path).unwrap();
+        journal.push_str(&line[..line.len() / 2]);
+        std::fs::write(&path, journal).unwrap();
+
+        assert_eq!(ids(&replayed(&path)), vec![0, 1]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn old_logs_are_dropped() {
+        let expired =
+            Timestamp::from_unix_timestamp(Timestamp::now().unix_timestamp() - MAX_AGE_SECS - 60)
+                .unwrap();
+        let path = journal("expired", &[queued_at(0, expired), queued(1)]);
+
+        let pending = replayed(&path);
+        assert_eq!(ids(&pending), vec![1]);
+        assert!(pending.outage_since.is_some());
+        assert_eq!(
+            pending.missed.get(&GUILD).map(|missed| missed.dropped),
+            Some(1)
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn only_the_newest_logs_are_kept() {
+        let records: Vec<Record> = (0..MAX_PENDING as u64 + 2).map(queued).collect();
+        let path = journal("full", &records);
+
+        let pending = replayed(&path);
+        assert_eq!(pending.logs.len(), MAX_PENDING);
+        assert_eq!(pending.logs.front().map(|log| log.id), Some(2));
+        assert_eq!(
+            pending.missed.get(&GUILD).map(|missed| missed.dropped),
+            Some(2)
+        );
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use serenity::client::Context;
+use serenity::model::id::{ChannelId, GuildId};
+
+use super::LogChannelError;
+use crate::readiness::{is_enabled, Feature};
+use crate::store::errors::StoreError;
+use crate::store::{get_store, BotStore};
+
+/// Every kind of guild log, each can be routed to its own channels with `/log-routes`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum LogType {
+    MemberJoin,
+    MemberLeave,
+    MemberKick,
+    MemberBan,
+    MemberUnban,
+    RoleChange,
+    NicknameChange,
+    ServerAvatarChange,
+    TimeoutChange,
+    MemberVerified,
+    MemberUpdate,
+    UsernameChange,
+    AvatarChange,
+    VoiceJoin,
+    VoiceLeave,
+    VoiceMove,
+    VoiceServerMute,
+    VoiceSelfMute,
+    VoiceStream,
+    MessageEdit,
+    MessageDelete,
+    MessageBulkDelete,
+    CommandDenied,
+    BotStarted,
+    BotOffline,
+    LogOutage,
+}
+
+/// The channel a log type goes to until it is routed somewhere else
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DefaultChannel {
+    Major,
+    Minor,
+    /// Too noisy for most guilds, only posted once routed with `/log-routes`
+    Off,
+}
+
+impl DefaultChannel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DefaultChannel::Major => "Major log channel",
+            DefaultChannel::Minor => "Minor log channel",
+            DefaultChannel::Off => "Off",
+        }
+    }
+
+    fn feature(&self) -> Option<Feature> {
+        match self {
+            DefaultChannel::Major => Some(Feature::MajorLogChannel),
+            DefaultChannel::Minor => Some(Feature::MinorLogChannel),
+            DefaultChannel::Off => None,
+        }
+    }
+
+    pub async fn configured(
+        &self,
+        store: &dyn BotStore,
+        guild_id: GuildId,
+    ) -> Result<Option<String>, StoreError> {
+        let channel_id = match self {
+            DefaultChannel::Major => store.get_major_log_channel(guild_id).await?,
+            DefaultChannel::Minor => store.get_minor_log_channel(guild_id).await?,
+            DefaultChannel::Off => None,
+        };
+        Ok(channel_id)
+    }
+}
+
+impl LogType {
+    /// Every log type, in the order `/log-routes list` shows them
+    pub const ALL: [LogType; 26] = [
+        LogType::MemberJoin,
+        LogType::MemberLeave,
+        LogType::MemberKick,
+        LogType::MemberBan,
+        LogType::MemberUnban,
+        LogType::RoleChange,
+        LogType::NicknameChange,
+        LogType::ServerAvatarChange,
+        LogType::TimeoutChange,
+        LogType::MemberVerified,
+        LogType::MemberUpdate,
+        LogType::UsernameChange,
+        LogType::AvatarChange,
+        LogType::VoiceJoin,
+        LogType::VoiceLeave,
+        LogType::VoiceMove,
+        LogType::VoiceServerMute,
+        LogType::VoiceSelfMute,
+        LogType::VoiceStream,
+        LogType::MessageEdit,
+        LogType::MessageDelete,
+        LogType::MessageBulkDelete,
+        LogType::CommandDenied,
+        LogType::BotStarted,
+        LogType::BotOffline,
+        LogType::LogOutage,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|log_type| log_type.name() == name)
+    }
+
+    /// Key in the routing table and the `/log-routes` choice value
+    pub fn name(&self) -> &'static str {
+        match self {
+            LogType::MemberJoin => "member-join",
+            LogType::MemberLeave => "member-leave",
+            LogType::MemberKick => "member-kick",
+            LogType::MemberBan => "member-ban",
+            LogType::MemberUnban => "member-unban",
+            LogType::RoleChange => "role-change",
+            LogType::NicknameChange => "nickname-change",
+            LogType::ServerAvatarChange => "server-avatar-change",
+            LogType::TimeoutChange => "timeout-change",
+            LogType::MemberVerified => "member-verified",
+            LogType::MemberUpdate => "member-update",
+            LogType::UsernameChange => "username-change",
+            LogType::AvatarChange => "avatar-change",
+            LogType::VoiceJoin => "voice-join",
+            LogType::VoiceLeave => "voice-leave",
+            LogType::VoiceMove => "voice-move",
+            LogType::VoiceServerMute => "voice-server-mute",
+            LogType::VoiceSelfMute => "voice-self-mute",
+            LogType::VoiceStream => "voice-stream",
+            LogType::MessageEdit => "message-edit",
+            LogType::MessageDelete => "message-delete",
+            LogType::MessageBulkDelete => "message-bulk-delete",
+            LogType::CommandDenied => "command-denied",
+            LogType::BotStarted => "bot-started",
+            LogType::BotOffline => "bot-offline",
+            LogType::LogOutage => "log-outage",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogType::MemberJoin => "Member joined",
+            LogType::MemberLeave => "Member left",
+            LogType::MemberKick => "Member kicked",
+            LogType::MemberBan => "Member banned",
+            LogType::MemberUnban => "Member unbanned",
+            LogType::RoleChange => "Roles updated",
+            LogType::NicknameChange => "Nickname changed",
+            LogType::ServerAvatarChange => "Server avatar changed",
+            LogType::TimeoutChange => "Timeout changed",
+            LogType::MemberVerified => "Member verified",
+            LogType::MemberUpdate => "Member updated, not cached",
+            LogType::UsernameChange => "Username changed",
+            LogType::AvatarChange => "Avatar changed",
+            LogType::VoiceJoin => "Voice joined",
+            LogType::VoiceLeave => "Voice left",
+            LogType::VoiceMove => "Voice moved",
+            LogType::VoiceServerMute => "Server muted or deafened",
+            LogType::VoiceSelfMute => "Self muted or deafened",
+            LogType::VoiceStream => "Streaming or camera",
+            LogType::MessageEdit => "Message edited",
+            LogType::MessageDelete => "Message deleted",
+            LogType::MessageBulkDelete => "Messages bulk deleted",
+            LogType::CommandDenied => "Command denied",
+            LogType::BotStarted => "Bot started",
+            LogType::BotOffline => "Bot offline",
+            LogType::LogOutage => "Logs delayed",
+        }
+    }
+
+    pub fn default_channel(&self) -> DefaultChannel {
+        match self {
+            LogType::MemberJoin
+            | LogType::MemberLeave
+            | LogType::MemberKick
+            | LogType::MemberBan
+            | LogType::MemberUnban
+            | LogType::RoleChange
+            | LogType::TimeoutChange
+            | LogType::VoiceServerMute
+            | LogType::BotStarted
+            | LogType::LogOutage => DefaultChannel::Major,
+            LogType::NicknameChange
+            | LogType::ServerAvatarChange
+            | LogType::MemberVerified
+            | LogType::MemberUpdate
+            | LogType::UsernameChange
+            | LogType::AvatarChange
+            | LogType::VoiceJoin
+            | LogType::VoiceLeave
+            | LogType::VoiceMove
+            | LogType::MessageEdit
+            | LogType::MessageDelete
+            | LogType::MessageBulkDelete
+            | LogType::CommandDenied
+            | LogType::BotOffline => DefaultChannel::Minor,
+            LogType::VoiceSelfMute | LogType::VoiceStream => DefaultChannel::Off,
+        }
+    }
+}
+
+/// Where `log_type` is routed in `guild_id`, `None` when it uses its default channel
+async fn configured_route(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    log_type: LogType,
+) -> Result<Option<Vec<ChannelId>>, LogChannelError> {
+    let routes = store.list_log_routes(guild_id).await?;
+
+    match routes.get(log_type.name()) {
+        Some(channel_ids) => Ok(Some(parse_channel_ids(channel_ids)?)),
+        None => Ok(None),
+    }
+}
+
+/// Every channel `log_type` is posted to in `guild_id`, empty when it is muted
+pub async fn resolve_route(
+    ctx: &Context,
+    guild_id: GuildId,
+    log_type: LogType,
+) -> Result<Vec<ChannelId>, LogChannelError> {
+    let store = get_store(ctx).await?;
+
+    if let Some(channel_ids) = configured_route(store.as_ref(), guild_id, log_type).await? {
+        return Ok(channel_ids);
+    }
+
+    let default = log_type.default_channel();
+    let feature = match default.feature() {
+        Some(feature) => feature,
+        None => return Ok(Vec::new()),
+    };
+    if !is_enabled(ctx, guild_id, feature).await {
+        return Err(LogChannelError::Disabled(default.label().to_string()));
+    }
+
+    match default.configured(store.as_ref(), guild_id).await? {
+        Some(value) => match value.parse::<u64>() {
+            Ok(value) => Ok(vec![ChannelId(value)]),
+            Err(_) => Err(LogChannelError::Other(format!(
+                "Could not parse {} id into u64",
+                default.label().to_lowercase()
+            ))),
+        },
+        None => Err(LogChannelError::NotConfigured(default.label().to_string())),
+    }
+}
+
+/// Channel ids are stored space separated, an empty list mutes the log type
+fn parse_channel_ids(channel_ids: &str) -> Result<Vec<ChannelId>, LogChannelError> {
+    channel_ids
+        .split_whitespace()
+        .map(|id| match id.parse::<u64>() {
+            Ok(id) => Ok(ChannelId(id)),
+            Err(_) => Err(LogChannelError::Other(format!(
+                "Could not parse routed channel id `{}` into u64",
+                id
+            ))),
+        })
+        .collect()
+}
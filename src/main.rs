@@ -1,23 +1,44 @@
 use std::env;
+use std::sync::Arc;
 
+use mythicc_bot::api::{self, Health};
 use mythicc_bot::events;
-use mythicc_bot::redis_client;
+use mythicc_bot::log_channel::queue;
+use mythicc_bot::metrics;
+use mythicc_bot::sharding::ShardMode;
+use mythicc_bot::shutdown::{self, Shutdown};
+use mythicc_bot::store;
+use mythicc_bot::utils::logging;
 
 use serenity::async_trait;
-use serenity::model::guild::Member;
+use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
+use serenity::gateway::ConnectionStage;
+use serenity::model::guild::{Guild, Member};
 
 use serenity::model::application::interaction::Interaction;
+use serenity::model::channel::Message;
+use serenity::model::event::{MessageUpdateEvent, ResumedEvent};
 use serenity::model::gateway::Ready;
 
-use serenity::model::prelude::GuildId;
+use serenity::model::prelude::{ChannelId, GuildId, MessageId};
 use serenity::model::user::User;
 use serenity::model::voice::VoiceState;
 use serenity::prelude::*;
-struct Handler;
+use tracing::{error, info};
+
+const DEFAULT_MESSAGE_CACHE_SIZE: usize = 100;
+
+struct Handler {
+    health: Arc<Health>,
+    shutdown: Arc<Shutdown>,
+}
 
 #[async_trait]
 impl EventHandler for Handler {
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return shutdown::reject_interaction(&ctx, &interaction).await;
+        };
         match interaction {
             Interaction::ApplicationCommand(application_command_interaction) => {
                 events::application_command::handle(ctx, application_command_interaction).await
@@ -28,11 +49,24 @@ impl EventHandler for Handler {
             Interaction::ModalSubmit(modal_submit_interaction) => {
                 events::modal_submit::handle(ctx, modal_submit_interaction).await
             }
+            Interaction::Autocomplete(autocomplete_interaction) => {
+                events::autocomplete::handle(ctx, autocomplete_interaction).await
+            }
             _ => (),
         };
     }
 
+    async fn guild_create(&self, ctx: Context, guild: Guild, is_new: bool) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
+        events::guild_create::handle(ctx, guild, is_new).await;
+    }
+
     async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
         events::guild_member_addition::handle(ctx, new_member).await;
     }
 
@@ -43,18 +77,30 @@ impl EventHandler for Handler {
         user: User,
         member_data: Option<Member>,
     ) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
         events::guild_member_removal::handle(ctx, guild_id, user, member_data).await;
     }
 
     async fn guild_ban_addition(&self, ctx: Context, guild_id: GuildId, banned_user: User) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
         events::guild_ban_addition::handle(ctx, guild_id, banned_user).await;
     }
 
     async fn guild_ban_removal(&self, ctx: Context, guild_id: GuildId, unbanned_user: User) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
         events::guild_ban_removal::handle(ctx, guild_id, unbanned_user).await;
     }
 
     async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
         events::voice_state_update::handle(ctx, old, new).await;
     }
 
@@ -64,21 +110,92 @@ impl EventHandler for Handler {
         old_if_available: Option<Member>,
         new: Member,
     ) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
         events::guild_member_update::handle(old_if_available, new, &ctx).await;
     }
 
+    async fn message_update(
+        &self,
+        ctx: Context,
+        old_if_available: Option<Message>,
+        new: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
+        events::message_update::handle(ctx, old_if_available, new, event).await;
+    }
+
+    async fn message_delete(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        deleted_message_id: MessageId,
+        guild_id: Option<GuildId>,
+    ) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
+        events::message_delete::handle(ctx, channel_id, deleted_message_id, guild_id).await;
+    }
+
+    async fn message_delete_bulk(
+        &self,
+        ctx: Context,
+        channel_id: ChannelId,
+        multiple_deleted_messages_ids: Vec<MessageId>,
+        guild_id: Option<GuildId>,
+    ) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
+        events::message_delete::handle_bulk(
+            ctx,
+            channel_id,
+            multiple_deleted_messages_ids,
+            guild_id,
+        )
+        .await;
+    }
+
     async fn cache_ready(&self, _ctx: Context, _guilds: Vec<GuildId>) {
-        println!("Cache Ready...");
+        info!("Cache Ready...");
+        self.health.set_cache_ready();
     }
 
     async fn ready(&self, ctx: Context, ready: Ready) {
+        let Some(_in_flight) = self.shutdown.track() else {
+            return;
+        };
+        let shard_id = ctx.shard_id;
+        self.health.set_shard_connected(shard_id, true);
+        if let Some([_, total]) = ready.shard {
+            self.health.set_total_shards(total);
+        }
+        self.shutdown.set_context(&ctx);
+        self.health.set_context(&ctx);
         events::start_up::handle(ctx, ready).await;
+        self.health.set_setup_complete(shard_id);
+    }
+
+    async fn resume(&self, ctx: Context, _resumed: ResumedEvent) {
+        self.health.set_shard_connected(ctx.shard_id, true);
+    }
+
+    async fn shard_stage_update(&self, _ctx: Context, event: ShardStageUpdateEvent) {
+        self.health
+            .set_shard_connected(event.shard_id.0, event.new == ConnectionStage::Connected);
     }
 }
 
 #[tokio::main]
 async fn main() {
     dotenv::dotenv().ok();
+    logging::init();
+
     // Configure the client with your Discord bot token in the environment.
     let token = env::var("DISCORD_TOKEN").expect("Expected a token in the environment");
 
@@ -90,21 +207,73 @@ async fn main() {
         | GatewayIntents::GUILD_PRESENCES
         | GatewayIntents::GUILD_MEMBERS;
 
+    let shard_mode = match ShardMode::from_env() {
+        Ok(shard_mode) => shard_mode,
+        Err(why) => {
+            error!("{}", why);
+            return;
+        }
+    };
+
+    // Logs a previous run could not post are sent once the first shard is ready
+    queue::open();
+
+    // Open the configured store that every handler pulls from `ctx.data`
+    let bot_store = match store::from_config() {
+        Ok(bot_store) => bot_store,
+        Err(why) => {
+            error!("Store error: {}", why);
+            return;
+        }
+    };
+
+    if let Some(addr) = metrics::configured_addr() {
+        tokio::spawn(metrics::serve(addr));
+    }
+
+    // Health checks and the admin API run next to the gateway client
+    let health = Arc::new(Health::default());
+    if let Some(count) = shard_mode.local_shards() {
+        health.set_expected_shards(count);
+    }
+    let shutdown = Arc::new(Shutdown::default());
+    tokio::spawn(api::serve(bot_store.clone(), health.clone()));
+
+    // Messages kept per channel, edit and delete logs can only show content that was cached
+    let message_cache_size = env::var("MESSAGE_CACHE_SIZE")
+        .ok()
+        .and_then(|size| size.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_MESSAGE_CACHE_SIZE);
+
     // Build our client.
     let mut client = Client::builder(token, intents)
-        .event_handler(Handler)
+        .cache_settings(|settings| settings.max_messages(message_cache_size))
+        .event_handler(Handler {
+            health,
+            shutdown: shutdown.clone(),
+        })
+        .type_map_insert::<store::Store>(bot_store)
         .await
         .expect("Error creating client");
 
-    // Check to make sure we can connect to redis, then drop the connection
-    let check_connection = redis_client::connect();
-    drop(check_connection);
+    let shutdown_task = tokio::spawn(shutdown::on_signal(
+        shutdown.clone(),
+        client.shard_manager.clone(),
+    ));
 
-    // Finally, start a single shard, and start listening to events.
+    // Finally, start the shards and start listening to events.
     //
     // Shards will automatically attempt to reconnect, and will perform
     // exponential backoff until it reconnects.
-    if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+    info!("Starting {}", shard_mode);
+    if let Err(why) = shard_mode.start(&mut client).await {
+        error!("Client error: {:?}", why);
+    }
+
+    // `start` returns once the shards are shut down, or on an error when nobody asked to stop
+    if shutdown.is_started() {
+        let _ = shutdown_task.await;
+    } else {
+        shutdown_task.abort();
     }
 }
@@ -1,9 +1,9 @@
-use self::errors::ComponentInteractionError;
 use crate::events::message_component::{
     MessageComponentDataBundle, MessageComponentResponseBundle,
 };
+use crate::interaction_error::InteractionError;
+use async_trait::async_trait;
 
-pub mod errors;
 pub mod game_add;
 pub mod game_add_reply;
 pub mod game_remove;
@@ -13,24 +13,53 @@ pub mod test_modal;
 pub mod test_multiple_select;
 pub mod test_single_select;
 
+/// Handles interactions with message components created with `custom_id`
+#[async_trait]
+pub trait ComponentHandler: Send + Sync {
+    fn custom_id(&self) -> &'static str;
+
+    async fn execute(
+        &self,
+        data_bundle: &mut MessageComponentDataBundle,
+    ) -> Result<MessageComponentResponseBundle, InteractionError>;
+}
+
+pub struct ComponentRegistry {
+    handlers: &'static [&'static dyn ComponentHandler],
+}
+
+impl ComponentRegistry {
+    pub fn find(&self, custom_id: &str) -> Option<&'static dyn ComponentHandler> {
+        self.handlers
+            .iter()
+            .find(|handler| handler.custom_id() == custom_id)
+            .copied()
+    }
+}
+
+pub static COMPONENTS: ComponentRegistry = ComponentRegistry {
+    handlers: &[
+        // Test components
+        &test_single_select::TestSingleSelect,
+        &test_multiple_select::TestMultipleSelect,
+        &test_button_message::TestButtonMessage,
+        &test_modal::TestModal,
+        // Pick your games
+        &game_remove::GameRemove,
+        &game_add::GameAdd,
+        &game_remove_reply::GameRemoveReply,
+        &game_add_reply::GameAddReply,
+    ],
+};
+
 pub async fn execute_command(
     data_bundle: &mut MessageComponentDataBundle,
-) -> Result<MessageComponentResponseBundle, ComponentInteractionError> {
-    let command_id = data_bundle.interaction.data.custom_id.as_str();
-
-    match command_id {
-        // Test commands
-        "test-single-select" => test_single_select::execute(data_bundle).await,
-        "test-multiple-select" => test_multiple_select::execute(data_bundle).await,
-        "test-button-message" => test_button_message::execute(data_bundle).await,
-        "test-modal" => test_modal::execute(data_bundle).await,
-        "pick-games-remove" => game_remove::execute(data_bundle).await,
-        "pick-games-add" => game_add::execute(data_bundle).await,
-        "game-remove-reply" => game_remove_reply::execute(data_bundle).await,
-        "game-add-reply" => game_add_reply::execute(data_bundle).await,
-
-        // No match
-        _ => Ok(MessageComponentResponseBundle {
+) -> Result<MessageComponentResponseBundle, InteractionError> {
+    let custom_id = data_bundle.interaction.data.custom_id.clone();
+
+    match COMPONENTS.find(&custom_id) {
+        Some(handler) => handler.execute(data_bundle).await,
+        None => Ok(MessageComponentResponseBundle {
             message: Some("Message Component response removed or not implemented".to_string()),
             modal: None,
         }),
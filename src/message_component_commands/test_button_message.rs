@@ -1,15 +1,27 @@
 use crate::{
     events::message_component::{MessageComponentDataBundle, MessageComponentResponseBundle},
-    message_component_commands::errors::ComponentInteractionError,
+    interaction_error::InteractionError,
+    message_component_commands::ComponentHandler,
 };
+use async_trait::async_trait;
 
-pub async fn execute(
-    data_bundle: &mut MessageComponentDataBundle,
-) -> Result<MessageComponentResponseBundle, ComponentInteractionError> {
-    data_bundle.set_ephemeral(true);
+pub struct TestButtonMessage;
 
-    Ok(MessageComponentResponseBundle {
-        message: Some("Well hello there!".to_string()),
-        modal: None,
-    })
+#[async_trait]
+impl ComponentHandler for TestButtonMessage {
+    fn custom_id(&self) -> &'static str {
+        "test-button-message"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut MessageComponentDataBundle,
+    ) -> Result<MessageComponentResponseBundle, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        Ok(MessageComponentResponseBundle {
+            message: Some("Well hello there!".to_string()),
+            modal: None,
+        })
+    }
 }
@@ -2,35 +2,47 @@ use crate::{
     events::message_component::{
         MessageComponentDataBundle, MessageComponentResponseBundle, ModalSettings,
     },
-    message_component_commands::errors::ComponentInteractionError,
+    interaction_error::InteractionError,
+    message_component_commands::ComponentHandler,
 };
+use async_trait::async_trait;
 
 use serenity::{builder::CreateComponents, model::prelude::component::InputTextStyle};
 
-pub async fn execute(
-    data_bundle: &mut MessageComponentDataBundle,
-) -> Result<MessageComponentResponseBundle, ComponentInteractionError> {
-    data_bundle.set_ephemeral(true);
+pub struct TestModal;
 
-    let mut modal_components = CreateComponents::default();
-    modal_components.create_action_row(|row| {
-        row.create_input_text(|input| {
-            input.custom_id("message");
-            input.style(InputTextStyle::Short);
-            input.label("Message");
-            input.placeholder("Type your message here");
-            input.required(true)
-        })
-    });
+#[async_trait]
+impl ComponentHandler for TestModal {
+    fn custom_id(&self) -> &'static str {
+        "test-modal"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut MessageComponentDataBundle,
+    ) -> Result<MessageComponentResponseBundle, InteractionError> {
+        data_bundle.set_ephemeral(true);
 
-    let modal = ModalSettings::new(
-        "test-modal".to_string(),
-        "Test Modal".to_string(),
-        modal_components,
-    );
+        let mut modal_components = CreateComponents::default();
+        modal_components.create_action_row(|row| {
+            row.create_input_text(|input| {
+                input.custom_id("message");
+                input.style(InputTextStyle::Short);
+                input.label("Message");
+                input.placeholder("Type your message here");
+                input.required(true)
+            })
+        });
 
-    Ok(MessageComponentResponseBundle {
-        message: None,
-        modal: Some(modal),
-    })
+        let modal = ModalSettings::new(
+            "test-modal".to_string(),
+            "Test Modal".to_string(),
+            modal_components,
+        );
+
+        Ok(MessageComponentResponseBundle {
+            message: None,
+            modal: Some(modal),
+        })
+    }
 }
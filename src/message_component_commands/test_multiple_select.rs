@@ -1,17 +1,30 @@
 use crate::{
     events::message_component::{MessageComponentDataBundle, MessageComponentResponseBundle},
-    message_component_commands::errors::ComponentInteractionError,
+    interaction_error::InteractionError,
+    message_component_commands::ComponentHandler,
 };
+use async_trait::async_trait;
+use tracing::debug;
 
-pub async fn execute(
-    data_bundle: &mut MessageComponentDataBundle,
-) -> Result<MessageComponentResponseBundle, ComponentInteractionError> {
-    data_bundle.set_ephemeral(true);
+pub struct TestMultipleSelect;
 
-    let msg = format!("{:#?}", data_bundle.interaction.data.values);
-    println!("{}", msg);
-    Ok(MessageComponentResponseBundle {
-        message: Some(msg),
-        modal: None,
-    })
+#[async_trait]
+impl ComponentHandler for TestMultipleSelect {
+    fn custom_id(&self) -> &'static str {
+        "test-multiple-select"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut MessageComponentDataBundle,
+    ) -> Result<MessageComponentResponseBundle, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        let msg = format!("{:#?}", data_bundle.interaction.data.values);
+        debug!("{}", msg);
+        Ok(MessageComponentResponseBundle {
+            message: Some(msg),
+            modal: None,
+        })
+    }
 }
@@ -1,21 +1,33 @@
 use crate::{
     events::message_component::{MessageComponentDataBundle, MessageComponentResponseBundle},
-    message_component_commands::errors::ComponentInteractionError,
+    interaction_error::InteractionError,
+    message_component_commands::ComponentHandler,
 };
+use async_trait::async_trait;
 
-pub async fn execute(
-    data_bundle: &mut MessageComponentDataBundle,
-) -> Result<MessageComponentResponseBundle, ComponentInteractionError> {
-    data_bundle.set_ephemeral(true);
+pub struct TestSingleSelect;
 
-    match data_bundle.interaction.data.values.get(0) {
-        Some(value) => Ok(MessageComponentResponseBundle {
-            message: Some(value.to_owned()),
-            modal: None,
-        }),
-        None => Err(ComponentInteractionError::UnresolvedData(
-            "test-single-select".to_string(),
-            "Selected value".to_string(),
-        )),
+#[async_trait]
+impl ComponentHandler for TestSingleSelect {
+    fn custom_id(&self) -> &'static str {
+        "test-single-select"
+    }
+
+    async fn execute(
+        &self,
+        data_bundle: &mut MessageComponentDataBundle,
+    ) -> Result<MessageComponentResponseBundle, InteractionError> {
+        data_bundle.set_ephemeral(true);
+
+        match data_bundle.interaction.data.values.get(0) {
+            Some(value) => Ok(MessageComponentResponseBundle {
+                message: Some(value.to_owned()),
+                modal: None,
+            }),
+            None => Err(InteractionError::UnresolvedData(
+                "test-single-select".to_string(),
+                "Selected value".to_string(),
+            )),
+        }
     }
 }
@@ -0,0 +1,212 @@
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::Router;
+use prometheus::{
+    HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::env;
+use std::net::SocketAddr;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Everything the bot counts, served as Prometheus text on `/metrics`
+struct Metrics {
+    registry: Registry,
+    interactions: IntCounterVec,
+    interaction_duration: HistogramVec,
+    gateway_events: IntCounterVec,
+    redis_duration: HistogramVec,
+    discord_http_errors: IntCounterVec,
+    log_channel_failures: IntCounterVec,
+    fix_roles: IntCounterVec,
+    shard_connected: IntGaugeVec,
+    log_queue_depth: IntGauge,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let interactions = IntCounterVec::new(
+            Opts::new("interactions_total", "Interactions handled"),
+            &["kind", "name", "outcome"],
+        )
+        .unwrap();
+        let interaction_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "interaction_duration_seconds",
+                "Time from receiving an interaction to responding",
+            ),
+            &["kind", "name"],
+        )
+        .unwrap();
+        let gateway_events = IntCounterVec::new(
+            Opts::new("gateway_events_total", "Gateway events handled"),
+            &["event"],
+        )
+        .unwrap();
+        let redis_duration = HistogramVec::new(
+            HistogramOpts::new("redis_command_duration_seconds", "Redis command latency").buckets(
+                vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 3.0],
+            ),
+            &["outcome"],
+        )
+        .unwrap();
+        let discord_http_errors = IntCounterVec::new(
+            Opts::new("discord_http_errors_total", "Failed Discord HTTP requests"),
+            &["source"],
+        )
+        .unwrap();
+        let log_channel_failures = IntCounterVec::new(
+            Opts::new(
+                "log_channel_failures_total",
+                "Log channel posts that could not be delivered",
+            ),
+            &["log"],
+        )
+        .unwrap();
+        let fix_roles = IntCounterVec::new(
+            Opts::new(
+                "fix_roles_total",
+                "Game role lists that had to be checked against Discord",
+            ),
+            &["source"],
+        )
+        .unwrap();
+        let shard_connected = IntGaugeVec::new(
+            Opts::new(
+                "shard_connected",
+                "1 while the shard is connected to the gateway",
+            ),
+            &["shard"],
+        )
+        .unwrap();
+        let log_queue_depth = IntGauge::new(
+            "log_queue_depth",
+            "Log channel posts waiting in the log queue",
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(interactions.clone())).unwrap();
+        registry
+            .register(Box::new(interaction_duration.clone()))
+            .unwrap();
+        registry.register(Box::new(gateway_events.clone())).unwrap();
+        registry.register(Box::new(redis_duration.clone())).unwrap();
+        registry
+            .register(Box::new(discord_http_errors.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(log_channel_failures.clone()))
+            .unwrap();
+        registry.register(Box::new(fix_roles.clone())).unwrap();
+        registry
+            .register(Box::new(shard_connected.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(log_queue_depth.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            interactions,
+            interaction_duration,
+            gateway_events,
+            redis_duration,
+            discord_http_errors,
+            log_channel_failures,
+            fix_roles,
+            shard_connected,
+            log_queue_depth,
+        }
+    }
+}
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// `kind` is "command", "component", "modal" or "autocomplete".
+/// `outcome` is "ok", "denied", "unknown_command" or the `InteractionError::kind`,
+/// e.g. "argument_missing"
+pub fn record_interaction(kind: &str, name: &str, outcome: &str, duration: Duration) {
+    let metrics = metrics();
+    metrics
+        .interactions
+        .with_label_values(&[kind, name, outcome])
+        .inc();
+    metrics
+        .interaction_duration
+        .with_label_values(&[kind, name])
+        .observe(duration.as_secs_f64());
+}
+
+pub fn record_event(event: &str) {
+    metrics().gateway_events.with_label_values(&[event]).inc();
+}
+
+pub fn record_redis_call(outcome: &str, duration: Duration) {
+    metrics()
+        .redis_duration
+        .with_label_values(&[outcome])
+        .observe(duration.as_secs_f64());
+}
+
+pub fn record_discord_http_error(source: &str) {
+    metrics()
+        .discord_http_errors
+        .with_label_values(&[source])
+        .inc();
+}
+
+pub fn record_log_failure(log: &str) {
+    metrics()
+        .log_channel_failures
+        .with_label_values(&[log])
+        .inc();
+}
+
+pub fn record_fix_roles(source: &str) {
+    metrics().fix_roles.with_label_values(&[source]).inc();
+}
+
+pub fn record_shard_connected(shard_id: u64, connected: bool) {
+    metrics()
+        .shard_connected
+        .with_label_values(&[&shard_id.to_string()])
+        .set(connected as i64);
+}
+
+pub fn record_log_queue_depth(depth: usize) {
+    metrics().log_queue_depth.set(depth as i64);
+}
+
+async fn render() -> Result<String, StatusCode> {
+    TextEncoder::new()
+        .encode_to_string(&metrics().registry.gather())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+/// Where to serve `/metrics`, e.g. `METRICS_ADDR=127.0.0.1:9100`, nothing is served when unset
+pub fn configured_addr() -> Option<SocketAddr> {
+    env::var("METRICS_ADDR").ok()?.parse().ok()
+}
+
+pub async fn serve(addr: SocketAddr) {
+    let app = Router::new().route("/metrics", get(render));
+
+    let server = match axum::Server::try_bind(&addr) {
+        Ok(server) => server,
+        Err(why) => {
+            error!("Could not serve metrics on {}: {}", addr, why);
+            return;
+        }
+    };
+
+    info!("Serving metrics on http://{}/metrics", addr);
+    if let Err(why) = server.serve(app.into_make_service()).await {
+        error!("Metrics server error: {}", why);
+    }
+}
@@ -1,16 +1,41 @@
 use crate::events::modal_submit::ModalDataBundle;
+use crate::interaction_error::InteractionError;
+use async_trait::async_trait;
 
-use self::errors::ModalError;
-
-pub mod errors;
 pub mod test_modal;
 
-pub async fn process_modal_data(data_bundle: &mut ModalDataBundle) -> Result<String, ModalError> {
-    let modal_id = data_bundle.interaction.data.custom_id.as_str();
+/// Handles submissions of the modal opened with `custom_id`
+#[async_trait]
+pub trait ModalHandler: Send + Sync {
+    fn custom_id(&self) -> &'static str;
+
+    async fn process(&self, data_bundle: &mut ModalDataBundle) -> Result<String, InteractionError>;
+}
+
+pub struct ModalRegistry {
+    handlers: &'static [&'static dyn ModalHandler],
+}
+
+impl ModalRegistry {
+    pub fn find(&self, custom_id: &str) -> Option<&'static dyn ModalHandler> {
+        self.handlers
+            .iter()
+            .find(|handler| handler.custom_id() == custom_id)
+            .copied()
+    }
+}
+
+pub static MODALS: ModalRegistry = ModalRegistry {
+    handlers: &[&test_modal::TestModal],
+};
+
+pub async fn process_modal_data(
+    data_bundle: &mut ModalDataBundle,
+) -> Result<String, InteractionError> {
+    let custom_id = data_bundle.interaction.data.custom_id.clone();
 
-    match modal_id {
-        "test-modal" => test_modal::process(data_bundle).await,
-        // No match
-        _ => Ok("Modal response removed or not implemented".to_string()),
+    match MODALS.find(&custom_id) {
+        Some(handler) => handler.process(data_bundle).await,
+        None => Ok("Modal response removed or not implemented".to_string()),
     }
 }
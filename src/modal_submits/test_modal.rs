@@ -1,24 +1,35 @@
 use crate::events::modal_submit::ModalDataBundle;
+use async_trait::async_trait;
 use serenity::model::prelude::component::ActionRowComponent;
 
-use super::errors::ModalError;
+use super::ModalHandler;
+use crate::interaction_error::InteractionError;
 
-pub async fn process(data_bundle: &mut ModalDataBundle) -> Result<String, ModalError> {
-    let components = &data_bundle.interaction.data.components;
-    let action_row = components.get(0).unwrap();
-    let text_input = match action_row.components.get(0).unwrap() {
-        ActionRowComponent::InputText(input_text) => input_text,
-        _ => {
-            return Err(ModalError::UnresolvedData(
-                "test_modal".to_string(),
-                "InputText `message` is missing from the modal".to_string(),
-            ))
-        }
-    };
+pub struct TestModal;
 
-    let custom_id = &text_input.custom_id;
-    let value = &text_input.value;
+#[async_trait]
+impl ModalHandler for TestModal {
+    fn custom_id(&self) -> &'static str {
+        "test-modal"
+    }
 
-    let output = format!("Modal {}: {}", custom_id, value);
-    Ok(output)
+    async fn process(&self, data_bundle: &mut ModalDataBundle) -> Result<String, InteractionError> {
+        let components = &data_bundle.interaction.data.components;
+        let action_row = components.get(0).unwrap();
+        let text_input = match action_row.components.get(0).unwrap() {
+            ActionRowComponent::InputText(input_text) => input_text,
+            _ => {
+                return Err(InteractionError::UnresolvedData(
+                    "test_modal".to_string(),
+                    "InputText `message` is missing from the modal".to_string(),
+                ))
+            }
+        };
+
+        let custom_id = &text_input.custom_id;
+        let value = &text_input.value;
+
+        let output = format!("Modal {}: {}", custom_id, value);
+        Ok(output)
+    }
 }
@@ -0,0 +1,191 @@
+use serenity::client::Context;
+use serenity::model::guild::Member;
+use serenity::model::id::GuildId;
+use std::env;
+
+use crate::store::errors::StoreError;
+use crate::store::BotStore;
+
+/// How much of the bot a member may use, every level includes the ones below it
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum PermissionLevel {
+    Everyone,
+    Member,
+    Moderator,
+    Admin,
+    Owner,
+}
+
+impl PermissionLevel {
+    /// Levels that can be handed out with `/permissions`, owners are configured separately
+    pub const GRANTABLE: [PermissionLevel; 3] = [
+        PermissionLevel::Member,
+        PermissionLevel::Moderator,
+        PermissionLevel::Admin,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "everyone" => Some(PermissionLevel::Everyone),
+            "member" => Some(PermissionLevel::Member),
+            "moderator" => Some(PermissionLevel::Moderator),
+            "admin" => Some(PermissionLevel::Admin),
+            "owner" => Some(PermissionLevel::Owner),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PermissionLevel::Everyone => "everyone",
+            PermissionLevel::Member => "member",
+            PermissionLevel::Moderator => "moderator",
+            PermissionLevel::Admin => "admin",
+            PermissionLevel::Owner => "owner",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PermissionLevel::Everyone => "Everyone",
+            PermissionLevel::Member => "Member",
+            PermissionLevel::Moderator => "Moderator",
+            PermissionLevel::Admin => "Admin",
+            PermissionLevel::Owner => "Owner",
+        }
+    }
+}
+
+/// Users in `OWNER_IDS` (comma separated) own the bot in every guild
+pub fn configured_owner_ids() -> Vec<String> {
+    env::var("OWNER_IDS")
+        .unwrap_or_default()
+        .split(',')
+        .map(|id| id.trim())
+        .filter(|id| id.parse::<u64>().is_ok())
+        .map(|id| id.to_string())
+        .collect()
+}
+
+// The Discord guild owner owns the bot unless `OWNER_FROM_GUILD=false`
+fn owner_from_guild() -> bool {
+    env::var("OWNER_FROM_GUILD")
+        .map(|value| value != "false")
+        .unwrap_or(true)
+}
+
+/// The owner of the bot in `guild_id`, the last `/transfer-ownership` target,
+/// falling back to whoever owns the Discord guild
+pub async fn guild_owner(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+) -> Result<Option<String>, StoreError> {
+    if let Some(owner) = store.get_owner(guild_id).await? {
+        return Ok(Some(owner));
+    }
+
+    if !owner_from_guild() {
+        return Ok(None);
+    }
+
+    let owner_id = match guild_id.to_guild_cached(&ctx.cache) {
+        Some(guild) => Some(guild.owner_id),
+        None => guild_id
+            .to_partial_guild(&ctx.http)
+            .await
+            .ok()
+            .map(|guild| guild.owner_id),
+    };
+
+    Ok(owner_id.map(|id| id.to_string()))
+}
+
+/// Every owner in `guild_id`, the guild owner first
+pub async fn list_owners(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+) -> Result<Vec<String>, StoreError> {
+    let mut owners: Vec<String> = guild_owner(ctx, store, guild_id)
+        .await?
+        .into_iter()
+        .collect();
+
+    for owner_id in configured_owner_ids() {
+        if !owners.contains(&owner_id) {
+            owners.push(owner_id);
+        }
+    }
+
+    Ok(owners)
+}
+
+pub async fn is_owner(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: &str,
+) -> Result<bool, StoreError> {
+    Ok(list_owners(ctx, store, guild_id)
+        .await?
+        .iter()
+        .any(|owner_id| owner_id == user_id))
+}
+
+/// Work out the highest level `member` holds in `guild_id`.
+///
+/// Levels come from the owner, the `admins` set, the bot admin role, the follower
+/// role (Member) and anything granted to the member or one of their roles.
+/// Grants are capped at Admin, owners are never granted
+pub async fn resolve_level(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    member: &Member,
+) -> Result<PermissionLevel, StoreError> {
+    let user_id = member.user.id.to_string();
+    let has_role = |role_id: &Option<String>| match role_id {
+        Some(role_id) => member
+            .roles
+            .iter()
+            .any(|role| role.0.to_string() == *role_id),
+        None => false,
+    };
+
+    if is_owner(ctx, store, guild_id, &user_id).await? {
+        return Ok(PermissionLevel::Owner);
+    }
+
+    let mut level = PermissionLevel::Everyone;
+
+    if has_role(&store.get_follower_role(guild_id).await?) {
+        level = level.max(PermissionLevel::Member);
+    }
+
+    if store.check_admin(guild_id, user_id.clone()).await?
+        || has_role(&store.get_bot_role(guild_id).await?)
+    {
+        level = level.max(PermissionLevel::Admin);
+    }
+
+    let user_permissions = store.list_user_permissions(guild_id).await?;
+    if let Some(granted) = user_permissions
+        .get(&user_id)
+        .and_then(|name| PermissionLevel::from_name(name))
+    {
+        level = level.max(granted.min(PermissionLevel::Admin));
+    }
+
+    let role_permissions = store.list_role_permissions(guild_id).await?;
+    for role in member.roles.iter() {
+        if let Some(granted) = role_permissions
+            .get(&role.0.to_string())
+            .and_then(|name| PermissionLevel::from_name(name))
+        {
+            level = level.max(granted.min(PermissionLevel::Admin));
+        }
+    }
+
+    Ok(level)
+}
@@ -0,0 +1,179 @@
+use serenity::client::Context;
+use serenity::model::id::GuildId;
+use serenity::prelude::TypeMapKey;
+use std::collections::HashMap;
+
+/// Outcome of a single startup check
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CheckStatus {
+    Passed,
+    Warning,
+    Failed,
+}
+
+impl CheckStatus {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CheckStatus::Passed => "✅",
+            CheckStatus::Warning => "⚠️",
+            CheckStatus::Failed => "❌",
+        }
+    }
+}
+
+/// Parts of the bot that are switched off when a check they rely on fails
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Feature {
+    FollowerRole,
+    GameRoles,
+    MajorLogChannel,
+    MinorLogChannel,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 4] = [
+        Feature::FollowerRole,
+        Feature::GameRoles,
+        Feature::MajorLogChannel,
+        Feature::MinorLogChannel,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Feature::FollowerRole => "Follower role on join",
+            Feature::GameRoles => "Game roles",
+            Feature::MajorLogChannel => "Major logs",
+            Feature::MinorLogChannel => "Minor logs",
+        }
+    }
+}
+
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub disables: Vec<Feature>,
+}
+
+/// Everything the bot found out about one guild while setting it up
+pub struct GuildReport {
+    pub guild_id: GuildId,
+    pub checks: Vec<Check>,
+}
+
+impl GuildReport {
+    pub fn new(guild_id: GuildId) -> Self {
+        Self {
+            guild_id,
+            checks: Vec::new(),
+        }
+    }
+
+    pub fn passed(&mut self, name: &str, detail: impl Into<String>) {
+        self.push(name, CheckStatus::Passed, detail, &[]);
+    }
+
+    pub fn warning(&mut self, name: &str, detail: impl Into<String>) {
+        self.push(name, CheckStatus::Warning, detail, &[]);
+    }
+
+    pub fn failed(&mut self, name: &str, detail: impl Into<String>, disables: &[Feature]) {
+        self.push(name, CheckStatus::Failed, detail, disables);
+    }
+
+    fn push(
+        &mut self,
+        name: &str,
+        status: CheckStatus,
+        detail: impl Into<String>,
+        disables: &[Feature],
+    ) {
+        self.checks.push(Check {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            disables: disables.to_vec(),
+        });
+    }
+
+    /// The worst status of any check, `Passed` when nothing was checked
+    pub fn status(&self) -> CheckStatus {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(CheckStatus::Passed)
+    }
+
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|check| check.disables.contains(&feature))
+    }
+
+    pub fn disabled_features(&self) -> Vec<Feature> {
+        let mut features: Vec<Feature> = Vec::new();
+        for feature in self.checks.iter().flat_map(|check| check.disables.iter()) {
+            if !features.contains(feature) {
+                features.push(*feature);
+            }
+        }
+        features
+    }
+
+    pub fn render(&self) -> String {
+        let mut content = "".to_string();
+        for check in self.checks.iter() {
+            content.push_str(&format!(
+                "{} **{}**: {}\n",
+                check.status.symbol(),
+                check.name,
+                check.detail
+            ));
+        }
+
+        let disabled = self.disabled_features();
+        if !disabled.is_empty() {
+            let labels: Vec<&str> = disabled.iter().map(|feature| feature.label()).collect();
+            content.push_str(&format!("\nDisabled: {}\n", labels.join(", ")));
+        }
+
+        content
+    }
+}
+
+/// Latest `GuildReport` for every guild, kept in the client data
+pub struct Readiness;
+
+impl TypeMapKey for Readiness {
+    type Value = HashMap<GuildId, GuildReport>;
+}
+
+pub async fn record_report(ctx: &Context, report: GuildReport) {
+    let mut data = ctx.data.write().await;
+    data.entry::<Readiness>()
+        .or_insert_with(HashMap::new)
+        .insert(report.guild_id, report);
+}
+
+/// Whether `guild_id` was already set up by this process
+pub async fn has_report(ctx: &Context, guild_id: GuildId) -> bool {
+    let data = ctx.data.read().await;
+    data.get::<Readiness>()
+        .map_or(false, |reports| reports.contains_key(&guild_id))
+}
+
+/// Whether `feature` can be used in `guild_id`.
+///
+/// Guilds that were not checked yet are treated as ready, the feature reports its own errors
+pub async fn is_enabled(ctx: &Context, guild_id: GuildId, feature: Feature) -> bool {
+    let data = ctx.data.read().await;
+    match data
+        .get::<Readiness>()
+        .and_then(|reports| reports.get(&guild_id))
+    {
+        Some(report) => report.is_enabled(feature),
+        None => true,
+    }
+}
@@ -1,144 +1,523 @@
-use redis::{Commands, Iter, RedisError};
+use crate::metrics::record_redis_call;
+use redis::aio::ConnectionManager;
+use redis::{AsyncCommands, RedisError, RedisResult};
+use serenity::model::id::GuildId;
+use std::collections::HashMap;
 use std::env;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::time::timeout;
 
-const MASTER_ADMIN: &str = "224597366324461568";
-pub fn connect() -> redis::Connection {
+type ErrorMessage = String;
+
+// Set of every guild the bot has been set up in
+const GUILDS_KEY: &str = "guilds";
+
+// Keys that were global before the bot served more than one guild
+const LEGACY_KEYS: [&str; 6] = [
+    "games",
+    "admins",
+    "bot admin role",
+    "follower role",
+    "major log channel",
+    "minor log channel",
+];
+
+// How long we wait for Redis before giving up, so a slow Redis never stalls the gateway loop
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Error, Debug)]
+pub enum RedisClientError {
+    #[error("Invalid configuration - `{0}`")]
+    Config(ErrorMessage),
+
+    #[error("Timed out after {0:?}")]
+    Timeout(Duration),
+
+    #[error("{0}")]
+    Query(#[from] RedisError),
+}
+
+/// Open the shared connection manager.
+///
+/// `ConnectionManager` is multiplexed, cheap to clone and transparently
+/// reconnects when the underlying connection drops.
+pub async fn connect() -> Result<ConnectionManager, RedisClientError> {
     dotenv::dotenv().ok();
-    let redis_host_name =
-        env::var("REDIS_HOSTNAME").expect("Missing environment variable REDIS_HOSTNAME");
+    let redis_host_name = env::var("REDIS_HOSTNAME").map_err(|_| {
+        RedisClientError::Config("Missing environment variable REDIS_HOSTNAME".to_string())
+    })?;
 
     let redis_password = env::var("REDIS_PASSWORD").unwrap_or_default();
 
     let is_tls: bool = env::var("TLS")
-        .expect("Missing environment variable TLS")
+        .map_err(|_| RedisClientError::Config("Missing environment variable TLS".to_string()))?
         .parse()
-        .expect("Environment variable TLS must be true or false");
+        .map_err(|_| {
+            RedisClientError::Config("Environment variable TLS must be true or false".to_string())
+        })?;
 
     let uri_scheme = if is_tls { "rediss" } else { "redis" };
 
     let redis_conn_url = format!("{}://:{}@{}", uri_scheme, redis_password, redis_host_name);
-    redis::Client::open(redis_conn_url)
-        .expect("Invalid connection URL")
-        .get_connection()
-        .expect("Failed to connect to Redis")
+    let client = redis::Client::open(redis_conn_url)
+        .map_err(|_| RedisClientError::Config("Invalid connection URL".to_string()))?;
+
+    match timeout(CONNECT_TIMEOUT, ConnectionManager::new(client)).await {
+        Ok(manager) => Ok(manager?),
+        Err(_) => Err(RedisClientError::Timeout(CONNECT_TIMEOUT)),
+    }
+}
+
+async fn with_timeout<T>(
+    query: impl Future<Output = RedisResult<T>>,
+) -> Result<T, RedisClientError> {
+    let started = Instant::now();
+    let result = match timeout(COMMAND_TIMEOUT, query).await {
+        Ok(result) => result.map_err(RedisClientError::Query),
+        Err(_) => Err(RedisClientError::Timeout(COMMAND_TIMEOUT)),
+    };
+
+    let outcome = match &result {
+        Ok(_) => "ok",
+        Err(RedisClientError::Timeout(_)) => "timeout",
+        Err(_) => "error",
+    };
+    record_redis_call(outcome, started.elapsed());
+
+    result
+}
+
+/// Namespace a key by guild, e.g. `guild:888888888888888888:games`
+fn guild_key(guild_id: GuildId, key: &str) -> String {
+    format!("guild:{}:{}", guild_id, key)
 }
 
-pub fn get_master_admin() -> &'static str {
-    MASTER_ADMIN
+pub async fn ping(conn: &mut ConnectionManager) -> Result<(), RedisClientError> {
+    with_timeout(redis::cmd("PING").query_async::<_, ()>(conn)).await
 }
 
-pub fn check_master_admin(conn: &mut redis::Connection) -> redis::RedisResult<()> {
-    let value: Result<bool, RedisError> = conn.sismember("admins", MASTER_ADMIN);
-    match value {
-        Ok(master_admin_found) => {
-            if !master_admin_found {
-                conn.sadd("admins", MASTER_ADMIN)?;
-            }
-            Ok(())
+/// Move the pre multi-guild global keys under `guild_id`, keeping anything already namespaced
+pub async fn adopt_legacy_keys(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<(), RedisClientError> {
+    for key in LEGACY_KEYS {
+        let legacy_exists: bool = with_timeout(conn.exists(key)).await?;
+        if legacy_exists {
+            let namespaced_key = guild_key(guild_id, key);
+            with_timeout(conn.rename_nx::<_, ()>(key, namespaced_key.as_str())).await?;
         }
-        Err(e) => Err(e),
     }
+    Ok(())
 }
 
-pub fn add_game(conn: &mut redis::Connection, game_role_id: String) -> redis::RedisResult<()> {
-    conn.sadd("games", game_role_id)?;
-    Ok(())
+pub async fn add_guild(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.sadd::<_, _, ()>(GUILDS_KEY, guild_id.to_string())).await
 }
 
-pub fn remove_game(conn: &mut redis::Connection, game_role_id: String) -> redis::RedisResult<()> {
-    conn.srem("games", game_role_id)?;
-    Ok(())
+pub async fn claim_first_run(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<bool, RedisClientError> {
+    with_timeout(conn.set_nx(guild_key(guild_id, "defaults applied"), "1")).await
 }
 
-pub fn list_games(conn: &mut redis::Connection) -> Result<Vec<String>, RedisError> {
-    let games_iter: Iter<String> = conn.sscan("games")?;
-    let mut games: Vec<String> = Vec::new();
+pub async fn list_guilds(conn: &mut ConnectionManager) -> Result<Vec<String>, RedisClientError> {
+    with_timeout(conn.smembers(GUILDS_KEY)).await
+}
 
-    for game in games_iter {
-        games.push(game);
-    }
+pub async fn add_game(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    game_role_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.sadd::<_, _, ()>(guild_key(guild_id, "games"), game_role_id)).await
+}
 
-    Ok(games)
+pub async fn remove_game(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    game_role_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.srem::<_, _, ()>(guild_key(guild_id, "games"), game_role_id)).await
 }
 
-pub fn add_admin(conn: &mut redis::Connection, admin_id: String) -> redis::RedisResult<()> {
-    conn.sadd("admins", admin_id)?;
-    Ok(())
+pub async fn list_games(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Vec<String>, RedisClientError> {
+    with_timeout(conn.smembers(guild_key(guild_id, "games"))).await
 }
 
-pub fn remove_admin(conn: &mut redis::Connection, admin_id: String) -> redis::RedisResult<()> {
-    conn.srem("admins", admin_id)?;
-    Ok(())
+pub async fn add_admin(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    admin_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.sadd::<_, _, ()>(guild_key(guild_id, "admins"), admin_id)).await
 }
 
-pub fn list_admins(conn: &mut redis::Connection) -> Result<Vec<String>, RedisError> {
-    let admins_iter: Iter<String> = conn.sscan("admins")?;
-    let mut admins: Vec<String> = Vec::new();
+pub async fn remove_admin(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    admin_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.srem::<_, _, ()>(guild_key(guild_id, "admins"), admin_id)).await
+}
 
-    for admin in admins_iter {
-        admins.push(admin);
-    }
+pub async fn list_admins(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Vec<String>, RedisClientError> {
+    with_timeout(conn.smembers(guild_key(guild_id, "admins"))).await
+}
 
-    Ok(admins)
+pub async fn check_admin(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    admin_id: String,
+) -> Result<bool, RedisClientError> {
+    with_timeout(conn.sismember(guild_key(guild_id, "admins"), admin_id)).await
 }
 
-pub fn check_admin(conn: &mut redis::Connection, admin_id: String) -> Result<bool, RedisError> {
-    let value: Result<bool, RedisError> = conn.sismember("admins", admin_id);
-    value
+pub async fn set_role_permission(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    role_id: String,
+    level: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hset::<_, _, _, ()>(guild_key(guild_id, "role permissions"), role_id, level))
+        .await
 }
 
-pub fn get_bot_role(conn: &mut redis::Connection) -> Result<Option<String>, RedisError> {
-    let value: Option<String> = conn.get("bot admin role")?;
-    Ok(value)
+pub async fn remove_role_permission(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    role_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hdel::<_, _, ()>(guild_key(guild_id, "role permissions"), role_id)).await
 }
 
-pub fn set_bot_role(conn: &mut redis::Connection, role_id: String) -> redis::RedisResult<()> {
-    conn.set("bot admin role", role_id)?;
-    Ok(())
+pub async fn list_role_permissions(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<HashMap<String, String>, RedisClientError> {
+    with_timeout(conn.hgetall(guild_key(guild_id, "role permissions"))).await
 }
 
-pub fn get_follower_role(conn: &mut redis::Connection) -> Result<Option<String>, RedisError> {
-    let value: Option<String> = conn.get("follower role")?;
-    Ok(value)
+pub async fn set_user_permission(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+    level: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hset::<_, _, _, ()>(guild_key(guild_id, "user permissions"), user_id, level))
+        .await
 }
 
-pub fn set_guild_id(conn: &mut redis::Connection, guild_id: String) -> redis::RedisResult<()> {
-    conn.set("guild id", guild_id)?;
-    Ok(())
+pub async fn remove_user_permission(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hdel::<_, _, ()>(guild_key(guild_id, "user permissions"), user_id)).await
 }
 
-pub fn get_guild_id(conn: &mut redis::Connection) -> Result<Option<String>, RedisError> {
-    let value: Option<String> = conn.get("guild id")?;
-    Ok(value)
+pub async fn list_user_permissions(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<HashMap<String, String>, RedisClientError> {
+    with_timeout(conn.hgetall(guild_key(guild_id, "user permissions"))).await
 }
 
-pub fn set_follower_role(conn: &mut redis::Connection, role_id: String) -> redis::RedisResult<()> {
-    conn.set("follower role", role_id)?;
-    Ok(())
+pub async fn get_owner(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Option<String>, RedisClientError> {
+    with_timeout(conn.get(guild_key(guild_id, "owner"))).await
+}
+
+pub async fn set_owner(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.set::<_, _, ()>(guild_key(guild_id, "owner"), user_id)).await
+}
+
+pub async fn get_bot_role(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Option<String>, RedisClientError> {
+    with_timeout(conn.get(guild_key(guild_id, "bot admin role"))).await
+}
+
+pub async fn set_bot_role(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    role_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.set::<_, _, ()>(guild_key(guild_id, "bot admin role"), role_id)).await
 }
 
-pub fn get_major_log_channel(conn: &mut redis::Connection) -> Result<Option<String>, RedisError> {
-    let value: Option<String> = conn.get("major log channel")?;
-    Ok(value)
+pub async fn reset_bot_role(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.del::<_, ()>(guild_key(guild_id, "bot admin role"))).await
 }
 
-pub fn set_major_log_channel(
-    conn: &mut redis::Connection,
+pub async fn get_follower_role(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Option<String>, RedisClientError> {
+    with_timeout(conn.get(guild_key(guild_id, "follower role"))).await
+}
+
+pub async fn set_follower_role(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    role_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.set::<_, _, ()>(guild_key(guild_id, "follower role"), role_id)).await
+}
+
+pub async fn reset_follower_role(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.del::<_, ()>(guild_key(guild_id, "follower role"))).await
+}
+
+pub async fn get_major_log_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Option<String>, RedisClientError> {
+    with_timeout(conn.get(guild_key(guild_id, "major log channel"))).await
+}
+
+pub async fn set_major_log_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
     channel_id: String,
-) -> redis::RedisResult<()> {
-    conn.set("major log channel", channel_id)?;
-    Ok(())
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.set::<_, _, ()>(guild_key(guild_id, "major log channel"), channel_id)).await
 }
 
-pub fn get_minor_log_channel(conn: &mut redis::Connection) -> Result<Option<String>, RedisError> {
-    let value: Option<String> = conn.get("minor log channel")?;
-    Ok(value)
+pub async fn reset_major_log_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.del::<_, ()>(guild_key(guild_id, "major log channel"))).await
 }
 
-pub fn set_minor_log_channel(
-    conn: &mut redis::Connection,
+pub async fn get_minor_log_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Option<String>, RedisClientError> {
+    with_timeout(conn.get(guild_key(guild_id, "minor log channel"))).await
+}
+
+pub async fn set_minor_log_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
     channel_id: String,
-) -> redis::RedisResult<()> {
-    conn.set("minor log channel", channel_id)?;
-    Ok(())
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.set::<_, _, ()>(guild_key(guild_id, "minor log channel"), channel_id)).await
+}
+
+pub async fn reset_minor_log_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.del::<_, ()>(guild_key(guild_id, "minor log channel"))).await
+}
+
+pub async fn set_log_route(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    log_type: String,
+    channel_ids: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hset::<_, _, _, ()>(guild_key(guild_id, "log routes"), log_type, channel_ids))
+        .await
+}
+
+pub async fn reset_log_route(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    log_type: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hdel::<_, _, ()>(guild_key(guild_id, "log routes"), log_type)).await
+}
+
+pub async fn list_log_routes(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<HashMap<String, String>, RedisClientError> {
+    with_timeout(conn.hgetall(guild_key(guild_id, "log routes"))).await
+}
+
+pub async fn set_log_digest(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    log_type: String,
+    minutes: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hset::<_, _, _, ()>(guild_key(guild_id, "log digests"), log_type, minutes))
+        .await
+}
+
+pub async fn reset_log_digest(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    log_type: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hdel::<_, _, ()>(guild_key(guild_id, "log digests"), log_type)).await
+}
+
+pub async fn list_log_digests(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<HashMap<String, String>, RedisClientError> {
+    with_timeout(conn.hgetall(guild_key(guild_id, "log digests"))).await
+}
+
+pub async fn get_open_voice_session(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+) -> Result<Option<String>, RedisClientError> {
+    with_timeout(conn.hget(guild_key(guild_id, "voice sessions"), user_id)).await
+}
+
+pub async fn set_open_voice_session(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+    session: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hset::<_, _, _, ()>(guild_key(guild_id, "voice sessions"), user_id, session))
+        .await
+}
+
+pub async fn remove_open_voice_session(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hdel::<_, _, ()>(guild_key(guild_id, "voice sessions"), user_id)).await
+}
+
+pub async fn list_open_voice_sessions(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<HashMap<String, String>, RedisClientError> {
+    with_timeout(conn.hgetall(guild_key(guild_id, "voice sessions"))).await
+}
+
+pub async fn add_voice_history(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+    session: String,
+    max_sessions: usize,
+    retention_secs: usize,
+) -> Result<(), RedisClientError> {
+    let key = guild_key(guild_id, &format!("voice history:{}", user_id));
+
+    with_timeout(
+        redis::pipe()
+            .atomic()
+            .lpush(&key, session)
+            .ignore()
+            .ltrim(&key, 0, max_sessions as isize - 1)
+            .ignore()
+            .expire(&key, retention_secs)
+            .ignore()
+            .query_async::<_, ()>(conn),
+    )
+    .await
+}
+
+pub async fn list_voice_history(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    user_id: String,
+) -> Result<Vec<String>, RedisClientError> {
+    let key = guild_key(guild_id, &format!("voice history:{}", user_id));
+    with_timeout(conn.lrange(key, 0, -1)).await
+}
+
+pub async fn add_voice_hub(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    channel_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.sadd::<_, _, ()>(guild_key(guild_id, "voice hubs"), channel_id)).await
+}
+
+pub async fn remove_voice_hub(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    channel_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.srem::<_, _, ()>(guild_key(guild_id, "voice hubs"), channel_id)).await
+}
+
+pub async fn list_voice_hubs(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<Vec<String>, RedisClientError> {
+    with_timeout(conn.smembers(guild_key(guild_id, "voice hubs"))).await
+}
+
+pub async fn set_temp_voice_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    channel_id: String,
+    owner_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hset::<_, _, _, ()>(
+        guild_key(guild_id, "temp voice channels"),
+        channel_id,
+        owner_id,
+    ))
+    .await
+}
+
+pub async fn remove_temp_voice_channel(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+    channel_id: String,
+) -> Result<(), RedisClientError> {
+    with_timeout(conn.hdel::<_, _, ()>(guild_key(guild_id, "temp voice channels"), channel_id))
+        .await
+}
+
+pub async fn list_temp_voice_channels(
+    conn: &mut ConnectionManager,
+    guild_id: GuildId,
+) -> Result<HashMap<String, String>, RedisClientError> {
+    with_timeout(conn.hgetall(guild_key(guild_id, "temp voice channels"))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_are_namespaced_per_guild() {
+        assert_eq!(guild_key(GuildId(1), "games"), "guild:1:games");
+        assert_ne!(
+            guild_key(GuildId(1), "games"),
+            guild_key(GuildId(2), "games")
+        );
+    }
 }
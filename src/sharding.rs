@@ -0,0 +1,130 @@
+use serenity::Client;
+use std::env;
+use std::fmt;
+
+/// How the gateway connection is split up, set with `SHARDS`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ShardMode {
+    /// One shard for every guild, the default
+    Single,
+    /// As many shards as Discord recommends
+    Auto,
+    /// `SHARDS=4`, every shard in this process
+    Fixed(u64),
+    /// `SHARDS=0-1/4`, shards 0 and 1 out of 4, the rest run elsewhere
+    Range { first: u64, last: u64, total: u64 },
+}
+
+impl fmt::Display for ShardMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShardMode::Single => write!(f, "a single shard"),
+            ShardMode::Auto => write!(f, "the recommended number of shards"),
+            ShardMode::Fixed(total) => write!(f, "{} shards", total),
+            ShardMode::Range { first, last, total } => {
+                write!(f, "shards {} to {} of {}", first, last, total)
+            }
+        }
+    }
+}
+
+impl ShardMode {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+        let invalid = || {
+            format!(
+                "SHARDS `{}` should be `auto`, a shard count like `4` or a range like `0-1/4`",
+                value
+            )
+        };
+
+        if value.is_empty() || value == "1" {
+            return Ok(ShardMode::Single);
+        }
+        if value == "auto" {
+            return Ok(ShardMode::Auto);
+        }
+
+        match value.split_once('/') {
+            None => match value.parse::<u64>() {
+                Ok(total) if total > 0 => Ok(ShardMode::Fixed(total)),
+                _ => Err(invalid()),
+            },
+            Some((range, total)) => {
+                let (first, last) = range.split_once('-').ok_or_else(invalid)?;
+                let first = first.parse::<u64>().map_err(|_| invalid())?;
+                let last = last.parse::<u64>().map_err(|_| invalid())?;
+                let total = total.parse::<u64>().map_err(|_| invalid())?;
+
+                if first > last || last >= total {
+                    return Err(invalid());
+                }
+                Ok(ShardMode::Range { first, last, total })
+            }
+        }
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        Self::parse(&env::var("SHARDS").unwrap_or_default())
+    }
+
+    /// How many shards this process runs, `None` for `auto` until Discord says
+    pub fn local_shards(&self) -> Option<u64> {
+        match self {
+            ShardMode::Single => Some(1),
+            ShardMode::Auto => None,
+            ShardMode::Fixed(total) => Some(*total),
+            ShardMode::Range { first, last, .. } => Some(last - first + 1),
+        }
+    }
+
+    /// Connect the shards and run until they are shut down
+    pub async fn start(self, client: &mut Client) -> serenity::Result<()> {
+        match self {
+            ShardMode::Single => client.start().await,
+            ShardMode::Auto => client.start_autosharded().await,
+            ShardMode::Fixed(total) => client.start_shards(total).await,
+            ShardMode::Range { first, last, total } => {
+                client.start_shard_range([first, last], total).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_mode() {
+        assert_eq!(ShardMode::parse(""), Ok(ShardMode::Single));
+        assert_eq!(ShardMode::parse("1"), Ok(ShardMode::Single));
+        assert_eq!(ShardMode::parse("auto"), Ok(ShardMode::Auto));
+        assert_eq!(ShardMode::parse(" 4 "), Ok(ShardMode::Fixed(4)));
+        assert_eq!(
+            ShardMode::parse("0-1/4"),
+            Ok(ShardMode::Range {
+                first: 0,
+                last: 1,
+                total: 4
+            })
+        );
+        assert_eq!(
+            ShardMode::parse("3-3/4"),
+            Ok(ShardMode::Range {
+                first: 3,
+                last: 3,
+                total: 4
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_values() {
+        for value in [
+            "0", "-1", "many", "Auto", "2-1/4", "0-4/4", "1/4", "0-1/", "a-b/4", "0-1/4/8",
+        ] {
+            assert!(ShardMode::parse(value).is_err(), "`{}` was accepted", value);
+        }
+    }
+}
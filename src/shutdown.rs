@@ -0,0 +1,203 @@
+use serenity::builder::CreateInteractionResponse;
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::client::Context;
+use serenity::model::application::interaction::{Interaction, InteractionResponseType};
+use serenity::prelude::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+use crate::log_channel::{digest, log_bot_offline, queue};
+use crate::log_error;
+use crate::metrics::record_discord_http_error;
+
+// How long running handlers get to finish their role updates and log posts
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(20);
+const ANNOUNCE_TIMEOUT: Duration = Duration::from_secs(10);
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Tracks running event handlers so a shutdown can wait for them
+#[derive(Default)]
+pub struct Shutdown {
+    started: AtomicBool,
+    in_flight: AtomicUsize,
+    idle: Notify,
+    context: std::sync::Mutex<Option<Context>>,
+}
+
+/// Held by a running handler, the shutdown waits until every one is dropped
+pub struct InFlight<'a> {
+    shutdown: &'a Shutdown,
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        if self.shutdown.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.shutdown.idle.notify_waiters();
+        }
+    }
+}
+
+impl Shutdown {
+    /// Register a handler, `None` once shutting down so no new work is started
+    pub fn track(&self) -> Option<InFlight<'_>> {
+        if self.is_started() {
+            return None;
+        }
+
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        Some(InFlight { shutdown: self })
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.started.load(Ordering::SeqCst)
+    }
+
+    /// Keep a `Context` around to post the offline notice with
+    pub fn set_context(&self, ctx: &Context) {
+        if let Ok(mut context) = self.context.lock() {
+            *context = Some(ctx.clone());
+        }
+    }
+
+    fn context(&self) -> Option<Context> {
+        self.context.lock().ok().and_then(|context| context.clone())
+    }
+
+    // `true` when every handler finished before `deadline`
+    async fn drain(&self, deadline: Duration) -> bool {
+        let drained = async {
+            loop {
+                // Register before checking, so a handler finishing in between still wakes us
+                let idle = self.idle.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+
+        timeout(deadline, drained).await.is_ok()
+    }
+}
+
+/// Answer an interaction that came in after the shutdown started, instead of leaving
+/// the user with "The application did not respond"
+pub async fn reject_interaction(ctx: &Context, interaction: &Interaction) {
+    let response = match interaction {
+        Interaction::ApplicationCommand(command) => {
+            command
+                .create_interaction_response(&ctx.http, restarting)
+                .await
+        }
+        Interaction::MessageComponent(component) => {
+            component
+                .create_interaction_response(&ctx.http, restarting)
+                .await
+        }
+        Interaction::ModalSubmit(modal) => {
+            modal
+                .create_interaction_response(&ctx.http, restarting)
+                .await
+        }
+        // Autocomplete suggestions have no room for a message
+        _ => return,
+    };
+
+    if let Err(why) = response {
+        record_discord_http_error("interaction_response");
+        warn!("Cannot tell the user the bot is restarting: {}", why);
+    }
+}
+
+fn restarting<'a>(
+    response: &mut CreateInteractionResponse<'a>,
+) -> &mut CreateInteractionResponse<'a> {
+    response
+        .kind(InteractionResponseType::ChannelMessageWithSource)
+        .interaction_response_data(|message| {
+            message
+                .ephemeral(true)
+                .content("The bot is restarting, try again shortly")
+        })
+}
+
+async fn wait_for_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+            }
+            Err(error) => {
+                log_error!(&error);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+// Tell every guild the bot is going away, minor log channels only
+async fn announce_offline(ctx: &Context) {
+    for guild_id in ctx.cache.guilds() {
+        if let Err(error) = log_bot_offline(guild_id, ctx).await {
+            log_error!(&error);
+        }
+    }
+}
+
+/// Wait for SIGTERM or SIGINT, then stop taking new events, let running handlers
+/// finish, post the offline notice and queued logs and disconnect every shard
+pub async fn on_signal(shutdown: Arc<Shutdown>, shard_manager: Arc<Mutex<ShardManager>>) {
+    wait_for_signal().await;
+    info!("Shutting down, no longer accepting events");
+
+    shutdown.started.store(true, Ordering::SeqCst);
+
+    // Handlers only queue their logs, they are posted after the offline notice
+    if !shutdown.drain(DRAIN_TIMEOUT).await {
+        warn!(
+            "{} handlers still running after {:?}, shutting down anyway",
+            shutdown.in_flight.load(Ordering::SeqCst),
+            DRAIN_TIMEOUT
+        );
+    }
+
+    if let Some(ctx) = shutdown.context() {
+        if timeout(ANNOUNCE_TIMEOUT, announce_offline(&ctx))
+            .await
+            .is_err()
+        {
+            warn!(
+                "Could not post every offline notice within {:?}",
+                ANNOUNCE_TIMEOUT
+            );
+        }
+    }
+
+    // Digests still collecting are posted early rather than lost
+    digest::flush_all().await;
+    if !queue::flush(FLUSH_TIMEOUT).await {
+        warn!(
+            "{} log entries not posted after {:?}, they are posted after the restart",
+            queue::pending(),
+            FLUSH_TIMEOUT
+        );
+    }
+
+    shard_manager.lock().await.shutdown_all().await;
+    info!("Every shard is disconnected");
+}
@@ -0,0 +1,236 @@
+use async_trait::async_trait;
+use serenity::client::Context;
+use serenity::model::id::GuildId;
+use serenity::prelude::TypeMapKey;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+
+use self::errors::StoreError;
+use self::memory_store::MemoryStore;
+use self::redis_store::RedisStore;
+
+pub mod errors;
+pub mod memory_store;
+pub mod redis_store;
+
+/// Everything the bot persists, independent of where it is stored.
+///
+/// Every value is namespaced by the guild it belongs to. `RedisStore` is used
+/// in production, `MemoryStore` lets the command and event logic run without
+/// any external services.
+#[async_trait]
+pub trait BotStore: Send + Sync {
+    /// Check the backend is reachable
+    async fn ping(&self) -> Result<(), StoreError>;
+
+    async fn add_guild(&self, guild_id: GuildId) -> Result<(), StoreError>;
+    async fn list_guilds(&self) -> Result<Vec<String>, StoreError>;
+
+    /// Claim data written before the bot was multi-guild for `guild_id`
+    async fn adopt_legacy_data(&self, _guild_id: GuildId) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    /// `true` only the first time it is called for `guild_id`, so first-run
+    /// defaults are applied once and never undo a later `/config reset`
+    async fn claim_first_run(&self, guild_id: GuildId) -> Result<bool, StoreError>;
+
+    /// The owner `/transfer-ownership` handed the guild to, if it was ever transferred
+    async fn get_owner(&self, guild_id: GuildId) -> Result<Option<String>, StoreError>;
+    async fn set_owner(&self, guild_id: GuildId, user_id: String) -> Result<(), StoreError>;
+
+    async fn add_game(&self, guild_id: GuildId, game_role_id: String) -> Result<(), StoreError>;
+    async fn remove_game(&self, guild_id: GuildId, game_role_id: String) -> Result<(), StoreError>;
+    async fn list_games(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError>;
+
+    async fn add_admin(&self, guild_id: GuildId, admin_id: String) -> Result<(), StoreError>;
+    async fn remove_admin(&self, guild_id: GuildId, admin_id: String) -> Result<(), StoreError>;
+    async fn list_admins(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError>;
+    async fn check_admin(&self, guild_id: GuildId, admin_id: String) -> Result<bool, StoreError>;
+
+    /// Permission levels granted to roles, keyed by role id
+    async fn set_role_permission(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+        level: String,
+    ) -> Result<(), StoreError>;
+    async fn remove_role_permission(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+    ) -> Result<(), StoreError>;
+    async fn list_role_permissions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError>;
+
+    /// Permission levels granted to individual users, keyed by user id
+    async fn set_user_permission(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        level: String,
+    ) -> Result<(), StoreError>;
+    async fn remove_user_permission(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<(), StoreError>;
+    async fn list_user_permissions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError>;
+
+    async fn get_bot_role(&self, guild_id: GuildId) -> Result<Option<String>, StoreError>;
+    async fn set_bot_role(&self, guild_id: GuildId, role_id: String) -> Result<(), StoreError>;
+    async fn reset_bot_role(&self, guild_id: GuildId) -> Result<(), StoreError>;
+
+    async fn get_follower_role(&self, guild_id: GuildId) -> Result<Option<String>, StoreError>;
+    async fn set_follower_role(&self, guild_id: GuildId, role_id: String)
+        -> Result<(), StoreError>;
+    async fn reset_follower_role(&self, guild_id: GuildId) -> Result<(), StoreError>;
+
+    async fn get_major_log_channel(&self, guild_id: GuildId) -> Result<Option<String>, StoreError>;
+    async fn set_major_log_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError>;
+    async fn reset_major_log_channel(&self, guild_id: GuildId) -> Result<(), StoreError>;
+
+    async fn get_minor_log_channel(&self, guild_id: GuildId) -> Result<Option<String>, StoreError>;
+    async fn set_minor_log_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError>;
+    async fn reset_minor_log_channel(&self, guild_id: GuildId) -> Result<(), StoreError>;
+
+    /// Channels each log type is posted to, keyed by log type.
+    ///
+    /// The value is a space separated list of channel ids, empty when the type is muted.
+    /// Types without an entry go to their default major or minor log channel
+    async fn set_log_route(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+        channel_ids: String,
+    ) -> Result<(), StoreError>;
+    async fn reset_log_route(&self, guild_id: GuildId, log_type: String) -> Result<(), StoreError>;
+    async fn list_log_routes(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError>;
+
+    /// Log types posted as one digest per window instead of one post per event,
+    /// keyed by log type with the window in minutes as the value
+    async fn set_log_digest(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+        minutes: String,
+    ) -> Result<(), StoreError>;
+    async fn reset_log_digest(&self, guild_id: GuildId, log_type: String)
+        -> Result<(), StoreError>;
+    async fn list_log_digests(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError>;
+
+    /// Voice sessions still going, keyed by user id with a JSON `VoiceSession` as the value
+    async fn get_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<Option<String>, StoreError>;
+    async fn set_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        session: String,
+    ) -> Result<(), StoreError>;
+    async fn remove_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<(), StoreError>;
+    async fn list_open_voice_sessions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError>;
+
+    /// Finished voice sessions of a user, newest first.
+    ///
+    /// Only the newest `max_sessions` are kept, and the history expires once
+    /// the user has no new session for `retention_secs`
+    async fn add_voice_history(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        session: String,
+        max_sessions: usize,
+        retention_secs: usize,
+    ) -> Result<(), StoreError>;
+    async fn list_voice_history(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<Vec<String>, StoreError>;
+
+    /// Voice channels that create a temporary channel for whoever joins them
+    async fn add_voice_hub(&self, guild_id: GuildId, channel_id: String) -> Result<(), StoreError>;
+    async fn remove_voice_hub(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError>;
+    async fn list_voice_hubs(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError>;
+
+    /// Temporary voice channels created from a hub, keyed by channel id with the owner as the value
+    async fn set_temp_voice_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+        owner_id: String,
+    ) -> Result<(), StoreError>;
+    async fn remove_temp_voice_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError>;
+    async fn list_temp_voice_channels(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError>;
+}
+
+pub struct Store;
+
+impl TypeMapKey for Store {
+    type Value = Arc<dyn BotStore>;
+}
+
+/// Build the storage backend selected by `STORE_BACKEND` (`redis` or `memory`)
+pub fn from_config() -> Result<Arc<dyn BotStore>, StoreError> {
+    let backend = env::var("STORE_BACKEND").unwrap_or_else(|_| "redis".to_string());
+
+    match backend.as_str() {
+        "redis" => Ok(Arc::new(RedisStore::new())),
+        "memory" => Ok(Arc::new(MemoryStore::default())),
+        other => Err(StoreError::Config(format!(
+            "Unknown STORE_BACKEND `{}`, expected `redis` or `memory`",
+            other
+        ))),
+    }
+}
+
+/// Grab a handle to the configured store from the client data
+pub async fn get_store(ctx: &Context) -> Result<Arc<dyn BotStore>, StoreError> {
+    let data = ctx.data.read().await;
+    match data.get::<Store>() {
+        Some(store) => Ok(store.clone()),
+        None => Err(StoreError::StoreMissing),
+    }
+}
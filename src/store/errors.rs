@@ -0,0 +1,16 @@
+use crate::redis_client::RedisClientError;
+use thiserror::Error;
+
+type ErrorMessage = String;
+
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("{0}")]
+    Redis(#[from] RedisClientError),
+
+    #[error("Store missing from client data")]
+    StoreMissing,
+
+    #[error("Invalid configuration - `{0}`")]
+    Config(ErrorMessage),
+}
@@ -0,0 +1,560 @@
+use async_trait::async_trait;
+use serenity::model::id::GuildId;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+use super::errors::StoreError;
+use super::BotStore;
+
+/// `BotStore` that keeps everything in process memory.
+///
+/// Nothing survives a restart, so this is meant for tests and local development.
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    guilds: HashMap<GuildId, GuildState>,
+}
+
+#[derive(Default)]
+struct GuildState {
+    games: HashSet<String>,
+    admins: HashSet<String>,
+    owner: Option<String>,
+    defaults_applied: bool,
+    role_permissions: HashMap<String, String>,
+    user_permissions: HashMap<String, String>,
+    bot_role: Option<String>,
+    follower_role: Option<String>,
+    major_log_channel: Option<String>,
+    minor_log_channel: Option<String>,
+    log_routes: HashMap<String, String>,
+    log_digests: HashMap<String, String>,
+    open_voice_sessions: HashMap<String, String>,
+    voice_history: HashMap<String, Vec<String>>,
+    voice_hubs: HashSet<String>,
+    temp_voice_channels: HashMap<String, String>,
+}
+
+impl MemoryStore {
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        // A poisoned lock only means another caller panicked mid-update,
+        // the sets themselves are still usable
+        match self.state.lock() {
+            Ok(state) => state,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+
+    fn with_guild<T>(&self, guild_id: GuildId, f: impl FnOnce(&mut GuildState) -> T) -> T {
+        f(self.state().guilds.entry(guild_id).or_default())
+    }
+
+    // Removing from a guild that has nothing stored has nothing to do
+    fn update_guild<T>(&self, guild_id: GuildId, f: impl FnOnce(&mut GuildState) -> T) {
+        if let Some(guild) = self.state().guilds.get_mut(&guild_id) {
+            f(guild);
+        }
+    }
+
+    // Reads don't add the guild, `list_guilds` only returns guilds something was written for
+    fn read_guild<T: Default>(&self, guild_id: GuildId, f: impl FnOnce(&GuildState) -> T) -> T {
+        self.state()
+            .guilds
+            .get(&guild_id)
+            .map(f)
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl BotStore for MemoryStore {
+    async fn ping(&self) -> Result<(), StoreError> {
+        Ok(())
+    }
+
+    async fn add_guild(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        self.state().guilds.entry(guild_id).or_default();
+        Ok(())
+    }
+
+    async fn list_guilds(&self) -> Result<Vec<String>, StoreError> {
+        Ok(self
+            .state()
+            .guilds
+            .keys()
+            .map(|id| id.to_string())
+            .collect())
+    }
+
+    async fn claim_first_run(&self, guild_id: GuildId) -> Result<bool, StoreError> {
+        Ok(self.with_guild(guild_id, |guild| {
+            !std::mem::replace(&mut guild.defaults_applied, true)
+        }))
+    }
+
+    async fn get_owner(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.owner.clone()))
+    }
+
+    async fn set_owner(&self, guild_id: GuildId, user_id: String) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.owner = Some(user_id));
+        Ok(())
+    }
+
+    async fn add_game(&self, guild_id: GuildId, game_role_id: String) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.games.insert(game_role_id));
+        Ok(())
+    }
+
+    async fn remove_game(&self, guild_id: GuildId, game_role_id: String) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.games.remove(&game_role_id));
+        Ok(())
+    }
+
+    async fn list_games(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.games.iter().cloned().collect()))
+    }
+
+    async fn add_admin(&self, guild_id: GuildId, admin_id: String) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.admins.insert(admin_id));
+        Ok(())
+    }
+
+    async fn remove_admin(&self, guild_id: GuildId, admin_id: String) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.admins.remove(&admin_id));
+        Ok(())
+    }
+
+    async fn list_admins(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.admins.iter().cloned().collect()))
+    }
+
+    async fn check_admin(&self, guild_id: GuildId, admin_id: String) -> Result<bool, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.admins.contains(&admin_id)))
+    }
+
+    async fn set_role_permission(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+        level: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| {
+            guild.role_permissions.insert(role_id, level)
+        });
+        Ok(())
+    }
+
+    async fn remove_role_permission(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+    ) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.role_permissions.remove(&role_id));
+        Ok(())
+    }
+
+    async fn list_role_permissions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.role_permissions.clone()))
+    }
+
+    async fn set_user_permission(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        level: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| {
+            guild.user_permissions.insert(user_id, level)
+        });
+        Ok(())
+    }
+
+    async fn remove_user_permission(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.user_permissions.remove(&user_id));
+        Ok(())
+    }
+
+    async fn list_user_permissions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.user_permissions.clone()))
+    }
+
+    async fn get_bot_role(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.bot_role.clone()))
+    }
+
+    async fn set_bot_role(&self, guild_id: GuildId, role_id: String) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.bot_role = Some(role_id));
+        Ok(())
+    }
+
+    async fn reset_bot_role(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.bot_role = None);
+        Ok(())
+    }
+
+    async fn get_follower_role(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.follower_role.clone()))
+    }
+
+    async fn set_follower_role(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.follower_role = Some(role_id));
+        Ok(())
+    }
+
+    async fn reset_follower_role(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.follower_role = None);
+        Ok(())
+    }
+
+    async fn get_major_log_channel(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.major_log_channel.clone()))
+    }
+
+    async fn set_major_log_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.major_log_channel = Some(channel_id));
+        Ok(())
+    }
+
+    async fn reset_major_log_channel(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.major_log_channel = None);
+        Ok(())
+    }
+
+    async fn get_minor_log_channel(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.minor_log_channel.clone()))
+    }
+
+    async fn set_minor_log_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.minor_log_channel = Some(channel_id));
+        Ok(())
+    }
+
+    async fn reset_minor_log_channel(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.minor_log_channel = None);
+        Ok(())
+    }
+
+    async fn set_log_route(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+        channel_ids: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| {
+            guild.log_routes.insert(log_type, channel_ids)
+        });
+        Ok(())
+    }
+
+    async fn reset_log_route(&self, guild_id: GuildId, log_type: String) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.log_routes.remove(&log_type));
+        Ok(())
+    }
+
+    async fn list_log_routes(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.log_routes.clone()))
+    }
+
+    async fn set_log_digest(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+        minutes: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| {
+            guild.log_digests.insert(log_type, minutes)
+        });
+        Ok(())
+    }
+
+    async fn reset_log_digest(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+    ) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.log_digests.remove(&log_type));
+        Ok(())
+    }
+
+    async fn list_log_digests(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.log_digests.clone()))
+    }
+
+    async fn get_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<Option<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| {
+            guild.open_voice_sessions.get(&user_id).cloned()
+        }))
+    }
+
+    async fn set_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        session: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| {
+            guild.open_voice_sessions.insert(user_id, session)
+        });
+        Ok(())
+    }
+
+    async fn remove_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.open_voice_sessions.remove(&user_id));
+        Ok(())
+    }
+
+    async fn list_open_voice_sessions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.open_voice_sessions.clone()))
+    }
+
+    // Nothing outlives the process here, so only the session limit applies
+    async fn add_voice_history(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        session: String,
+        max_sessions: usize,
+        _retention_secs: usize,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| {
+            let history = guild.voice_history.entry(user_id).or_default();
+            history.insert(0, session);
+            history.truncate(max_sessions);
+        });
+        Ok(())
+    }
+
+    async fn list_voice_history(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<Vec<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| {
+            guild
+                .voice_history
+                .get(&user_id)
+                .cloned()
+                .unwrap_or_default()
+        }))
+    }
+
+    async fn add_voice_hub(&self, guild_id: GuildId, channel_id: String) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| guild.voice_hubs.insert(channel_id));
+        Ok(())
+    }
+
+    async fn remove_voice_hub(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| guild.voice_hubs.remove(&channel_id));
+        Ok(())
+    }
+
+    async fn list_voice_hubs(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.voice_hubs.iter().cloned().collect()))
+    }
+
+    async fn set_temp_voice_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+        owner_id: String,
+    ) -> Result<(), StoreError> {
+        self.with_guild(guild_id, |guild| {
+            guild.temp_voice_channels.insert(channel_id, owner_id)
+        });
+        Ok(())
+    }
+
+    async fn remove_temp_voice_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        self.update_guild(guild_id, |guild| {
+            guild.temp_voice_channels.remove(&channel_id)
+        });
+        Ok(())
+    }
+
+    async fn list_temp_voice_channels(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(self.read_guild(guild_id, |guild| guild.temp_voice_channels.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permissions::PermissionLevel;
+
+    const GUILD: GuildId = GuildId(1);
+    const OTHER_GUILD: GuildId = GuildId(2);
+
+    fn sorted(mut values: Vec<String>) -> Vec<String> {
+        values.sort();
+        values
+    }
+
+    #[tokio::test]
+    async fn games_are_added_and_removed() {
+        let store = MemoryStore::default();
+        store.add_game(GUILD, "10".to_string()).await.unwrap();
+        store.add_game(GUILD, "11".to_string()).await.unwrap();
+        store.add_game(GUILD, "10".to_string()).await.unwrap();
+
+        assert_eq!(
+            sorted(store.list_games(GUILD).await.unwrap()),
+            vec!["10", "11"]
+        );
+
+        store.remove_game(GUILD, "10".to_string()).await.unwrap();
+        store.remove_game(GUILD, "12".to_string()).await.unwrap();
+        assert_eq!(store.list_games(GUILD).await.unwrap(), vec!["11"]);
+    }
+
+    #[tokio::test]
+    async fn admins_are_added_and_removed() {
+        let store = MemoryStore::default();
+        store.add_admin(GUILD, "20".to_string()).await.unwrap();
+
+        assert!(store.check_admin(GUILD, "20".to_string()).await.unwrap());
+        assert!(!store.check_admin(GUILD, "21".to_string()).await.unwrap());
+
+        store.remove_admin(GUILD, "20".to_string()).await.unwrap();
+        assert!(!store.check_admin(GUILD, "20".to_string()).await.unwrap());
+        assert!(store.list_admins(GUILD).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn permission_levels_are_stored_by_name() {
+        let store = MemoryStore::default();
+        let moderator = PermissionLevel::Moderator.name().to_string();
+        store
+            .set_role_permission(GUILD, "30".to_string(), moderator.clone())
+            .await
+            .unwrap();
+        store
+            .set_user_permission(GUILD, "31".to_string(), moderator)
+            .await
+            .unwrap();
+
+        let roles = store.list_role_permissions(GUILD).await.unwrap();
+        assert_eq!(
+            roles
+                .get("30")
+                .and_then(|name| PermissionLevel::from_name(name)),
+            Some(PermissionLevel::Moderator)
+        );
+
+        store
+            .remove_user_permission(GUILD, "31".to_string())
+            .await
+            .unwrap();
+        assert!(store.list_user_permissions(GUILD).await.unwrap().is_empty());
+        assert_eq!(store.list_role_permissions(GUILD).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn guilds_do_not_share_data() {
+        let store = MemoryStore::default();
+        store.add_game(GUILD, "10".to_string()).await.unwrap();
+        store.add_admin(GUILD, "20".to_string()).await.unwrap();
+        store
+            .set_follower_role(GUILD, "40".to_string())
+            .await
+            .unwrap();
+        store
+            .set_user_permission(GUILD, "31".to_string(), "admin".to_string())
+            .await
+            .unwrap();
+
+        assert!(store.list_games(OTHER_GUILD).await.unwrap().is_empty());
+        assert!(!store
+            .check_admin(OTHER_GUILD, "20".to_string())
+            .await
+            .unwrap());
+        assert_eq!(store.get_follower_role(OTHER_GUILD).await.unwrap(), None);
+        assert!(store
+            .list_user_permissions(OTHER_GUILD)
+            .await
+            .unwrap()
+            .is_empty());
+
+        store
+            .remove_game(OTHER_GUILD, "10".to_string())
+            .await
+            .unwrap();
+        assert_eq!(store.list_games(GUILD).await.unwrap(), vec!["10"]);
+    }
+
+    #[tokio::test]
+    async fn reads_do_not_add_guilds() {
+        let store = MemoryStore::default();
+        store.list_games(GUILD).await.unwrap();
+        store.get_owner(GUILD).await.unwrap();
+        store.remove_admin(GUILD, "20".to_string()).await.unwrap();
+
+        assert!(store.list_guilds().await.unwrap().is_empty());
+
+        store.add_game(GUILD, "10".to_string()).await.unwrap();
+        assert_eq!(store.list_guilds().await.unwrap(), vec!["1"]);
+    }
+
+    #[tokio::test]
+    async fn first_run_is_claimed_once_per_guild() {
+        let store = MemoryStore::default();
+
+        assert!(store.claim_first_run(GUILD).await.unwrap());
+        assert!(!store.claim_first_run(GUILD).await.unwrap());
+        assert!(store.claim_first_run(OTHER_GUILD).await.unwrap());
+    }
+}
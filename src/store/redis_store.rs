@@ -0,0 +1,435 @@
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use serenity::model::id::GuildId;
+use std::collections::HashMap;
+use tokio::sync::OnceCell;
+
+use super::errors::StoreError;
+use super::BotStore;
+use crate::redis_client;
+
+/// `BotStore` backed by the shared Redis connection.
+///
+/// The connection is opened on first use, so the bot can start (degraded)
+/// while Redis is down and picks it up as soon as it is reachable.
+#[derive(Default)]
+pub struct RedisStore {
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // `ConnectionManager` is multiplexed, so every call gets its own cheap handle
+    async fn connection(&self) -> Result<ConnectionManager, StoreError> {
+        let connection = self
+            .connection
+            .get_or_try_init(redis_client::connect)
+            .await?;
+        Ok(connection.clone())
+    }
+}
+
+#[async_trait]
+impl BotStore for RedisStore {
+    async fn ping(&self) -> Result<(), StoreError> {
+        Ok(redis_client::ping(&mut self.connection().await?).await?)
+    }
+
+    async fn add_guild(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        Ok(redis_client::add_guild(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn list_guilds(&self) -> Result<Vec<String>, StoreError> {
+        Ok(redis_client::list_guilds(&mut self.connection().await?).await?)
+    }
+
+    async fn claim_first_run(&self, guild_id: GuildId) -> Result<bool, StoreError> {
+        Ok(redis_client::claim_first_run(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn adopt_legacy_data(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        Ok(redis_client::adopt_legacy_keys(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn get_owner(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(redis_client::get_owner(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_owner(&self, guild_id: GuildId, user_id: String) -> Result<(), StoreError> {
+        Ok(redis_client::set_owner(&mut self.connection().await?, guild_id, user_id).await?)
+    }
+
+    async fn add_game(&self, guild_id: GuildId, game_role_id: String) -> Result<(), StoreError> {
+        Ok(redis_client::add_game(&mut self.connection().await?, guild_id, game_role_id).await?)
+    }
+
+    async fn remove_game(&self, guild_id: GuildId, game_role_id: String) -> Result<(), StoreError> {
+        Ok(
+            redis_client::remove_game(&mut self.connection().await?, guild_id, game_role_id)
+                .await?,
+        )
+    }
+
+    async fn list_games(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError> {
+        Ok(redis_client::list_games(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn add_admin(&self, guild_id: GuildId, admin_id: String) -> Result<(), StoreError> {
+        Ok(redis_client::add_admin(&mut self.connection().await?, guild_id, admin_id).await?)
+    }
+
+    async fn remove_admin(&self, guild_id: GuildId, admin_id: String) -> Result<(), StoreError> {
+        Ok(redis_client::remove_admin(&mut self.connection().await?, guild_id, admin_id).await?)
+    }
+
+    async fn list_admins(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError> {
+        Ok(redis_client::list_admins(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn check_admin(&self, guild_id: GuildId, admin_id: String) -> Result<bool, StoreError> {
+        Ok(redis_client::check_admin(&mut self.connection().await?, guild_id, admin_id).await?)
+    }
+
+    async fn set_role_permission(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+        level: String,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::set_role_permission(
+            &mut self.connection().await?,
+            guild_id,
+            role_id,
+            level,
+        )
+        .await?)
+    }
+
+    async fn remove_role_permission(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::remove_role_permission(&mut self.connection().await?, guild_id, role_id)
+                .await?,
+        )
+    }
+
+    async fn list_role_permissions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(redis_client::list_role_permissions(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_user_permission(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        level: String,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::set_user_permission(
+            &mut self.connection().await?,
+            guild_id,
+            user_id,
+            level,
+        )
+        .await?)
+    }
+
+    async fn remove_user_permission(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::remove_user_permission(&mut self.connection().await?, guild_id, user_id)
+                .await?,
+        )
+    }
+
+    async fn list_user_permissions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(redis_client::list_user_permissions(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn get_bot_role(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(redis_client::get_bot_role(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_bot_role(&self, guild_id: GuildId, role_id: String) -> Result<(), StoreError> {
+        Ok(redis_client::set_bot_role(&mut self.connection().await?, guild_id, role_id).await?)
+    }
+
+    async fn reset_bot_role(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        Ok(redis_client::reset_bot_role(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn get_follower_role(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(redis_client::get_follower_role(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_follower_role(
+        &self,
+        guild_id: GuildId,
+        role_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::set_follower_role(&mut self.connection().await?, guild_id, role_id)
+                .await?,
+        )
+    }
+
+    async fn reset_follower_role(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        Ok(redis_client::reset_follower_role(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn get_major_log_channel(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(redis_client::get_major_log_channel(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_major_log_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::set_major_log_channel(
+                &mut self.connection().await?,
+                guild_id,
+                channel_id,
+            )
+            .await?,
+        )
+    }
+
+    async fn reset_major_log_channel(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        Ok(redis_client::reset_major_log_channel(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn get_minor_log_channel(&self, guild_id: GuildId) -> Result<Option<String>, StoreError> {
+        Ok(redis_client::get_minor_log_channel(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_minor_log_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::set_minor_log_channel(
+                &mut self.connection().await?,
+                guild_id,
+                channel_id,
+            )
+            .await?,
+        )
+    }
+
+    async fn reset_minor_log_channel(&self, guild_id: GuildId) -> Result<(), StoreError> {
+        Ok(redis_client::reset_minor_log_channel(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_log_route(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+        channel_ids: String,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::set_log_route(
+            &mut self.connection().await?,
+            guild_id,
+            log_type,
+            channel_ids,
+        )
+        .await?)
+    }
+
+    async fn reset_log_route(&self, guild_id: GuildId, log_type: String) -> Result<(), StoreError> {
+        Ok(
+            redis_client::reset_log_route(&mut self.connection().await?, guild_id, log_type)
+                .await?,
+        )
+    }
+
+    async fn list_log_routes(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(redis_client::list_log_routes(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_log_digest(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+        minutes: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::set_log_digest(
+                &mut self.connection().await?,
+                guild_id,
+                log_type,
+                minutes,
+            )
+            .await?,
+        )
+    }
+
+    async fn reset_log_digest(
+        &self,
+        guild_id: GuildId,
+        log_type: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::reset_log_digest(&mut self.connection().await?, guild_id, log_type)
+                .await?,
+        )
+    }
+
+    async fn list_log_digests(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(redis_client::list_log_digests(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn get_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<Option<String>, StoreError> {
+        Ok(
+            redis_client::get_open_voice_session(&mut self.connection().await?, guild_id, user_id)
+                .await?,
+        )
+    }
+
+    async fn set_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        session: String,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::set_open_voice_session(
+            &mut self.connection().await?,
+            guild_id,
+            user_id,
+            session,
+        )
+        .await?)
+    }
+
+    async fn remove_open_voice_session(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::remove_open_voice_session(
+            &mut self.connection().await?,
+            guild_id,
+            user_id,
+        )
+        .await?)
+    }
+
+    async fn list_open_voice_sessions(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(redis_client::list_open_voice_sessions(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn add_voice_history(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+        session: String,
+        max_sessions: usize,
+        retention_secs: usize,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::add_voice_history(
+            &mut self.connection().await?,
+            guild_id,
+            user_id,
+            session,
+            max_sessions,
+            retention_secs,
+        )
+        .await?)
+    }
+
+    async fn list_voice_history(
+        &self,
+        guild_id: GuildId,
+        user_id: String,
+    ) -> Result<Vec<String>, StoreError> {
+        Ok(
+            redis_client::list_voice_history(&mut self.connection().await?, guild_id, user_id)
+                .await?,
+        )
+    }
+
+    async fn add_voice_hub(&self, guild_id: GuildId, channel_id: String) -> Result<(), StoreError> {
+        Ok(
+            redis_client::add_voice_hub(&mut self.connection().await?, guild_id, channel_id)
+                .await?,
+        )
+    }
+
+    async fn remove_voice_hub(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(
+            redis_client::remove_voice_hub(&mut self.connection().await?, guild_id, channel_id)
+                .await?,
+        )
+    }
+
+    async fn list_voice_hubs(&self, guild_id: GuildId) -> Result<Vec<String>, StoreError> {
+        Ok(redis_client::list_voice_hubs(&mut self.connection().await?, guild_id).await?)
+    }
+
+    async fn set_temp_voice_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+        owner_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::set_temp_voice_channel(
+            &mut self.connection().await?,
+            guild_id,
+            channel_id,
+            owner_id,
+        )
+        .await?)
+    }
+
+    async fn remove_temp_voice_channel(
+        &self,
+        guild_id: GuildId,
+        channel_id: String,
+    ) -> Result<(), StoreError> {
+        Ok(redis_client::remove_temp_voice_channel(
+            &mut self.connection().await?,
+            guild_id,
+            channel_id,
+        )
+        .await?)
+    }
+
+    async fn list_temp_voice_channels(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<String, String>, StoreError> {
+        Ok(redis_client::list_temp_voice_channels(&mut self.connection().await?, guild_id).await?)
+    }
+}
@@ -0,0 +1,425 @@
+use serenity::client::Context;
+use serenity::model::channel::{ChannelType, PermissionOverwrite, PermissionOverwriteType};
+use serenity::model::gateway::ActivityType;
+use serenity::model::guild::{Guild, Member};
+use serenity::model::id::{ChannelId, GuildId, RoleId, UserId};
+use serenity::model::Permissions;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::store::errors::StoreError;
+use crate::store::BotStore;
+
+type ErrorMessage = String;
+
+/// Discord renames a channel twice every 10 minutes, a third rename waits out the rest
+pub const RENAMES_PER_WINDOW: usize = 2;
+const RENAME_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// Recent renames by channel. A guild's `/vc` commands all reach the shard of that guild,
+// this process
+static RENAMES: OnceLock<Mutex<HashMap<ChannelId, Vec<Instant>>>> = OnceLock::new();
+
+#[derive(Error, Debug)]
+pub enum TempVoiceError {
+    #[error("Redis: {0}")]
+    RedisError(ErrorMessage),
+
+    #[error("Temp Voice Error: `{0}`")]
+    Other(ErrorMessage),
+}
+
+impl From<StoreError> for TempVoiceError {
+    fn from(error: StoreError) -> Self {
+        TempVoiceError::RedisError(error.to_string())
+    }
+}
+
+/// Temporary channels of `guild_id` and who owns them
+pub async fn temp_channels(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+) -> Result<HashMap<ChannelId, UserId>, StoreError> {
+    let channels = store.list_temp_voice_channels(guild_id).await?;
+
+    Ok(channels
+        .iter()
+        .filter_map(|(channel_id, owner_id)| {
+            match (channel_id.parse::<u64>(), owner_id.parse::<u64>()) {
+                (Ok(channel_id), Ok(owner_id)) => Some((ChannelId(channel_id), UserId(owner_id))),
+                _ => None,
+            }
+        })
+        .collect())
+}
+
+/// Count a rename of `channel_id`, or how long until Discord allows the next one
+pub fn claim_rename(channel_id: ChannelId) -> Result<(), Duration> {
+    let mut renames = RENAMES
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+
+    // Forget renames past the window, and channels left without any
+    let now = Instant::now();
+    renames.retain(|_, times| {
+        times.retain(|time| now.duration_since(*time) < RENAME_WINDOW);
+        !times.is_empty()
+    });
+
+    let times = renames.entry(channel_id).or_default();
+    if times.len() >= RENAMES_PER_WINDOW {
+        return Err(RENAME_WINDOW - now.duration_since(times[0]));
+    }
+    times.push(now);
+    Ok(())
+}
+
+/// The voice channel `user_id` is in right now, from the cache
+pub fn current_channel(ctx: &Context, guild_id: GuildId, user_id: UserId) -> Option<ChannelId> {
+    ctx.cache
+        .guild_field(guild_id, |guild| {
+            guild
+                .voice_states
+                .get(&user_id)
+                .and_then(|state| state.channel_id)
+        })
+        .flatten()
+}
+
+/// Everyone but bots in `channel_id` right now, from the cache
+pub fn members_in(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) -> Vec<UserId> {
+    ctx.cache
+        .guild_field(guild_id, |guild| {
+            guild
+                .voice_states
+                .values()
+                .filter(|state| state.channel_id == Some(channel_id))
+                .filter(|state| {
+                    let member = state
+                        .member
+                        .as_ref()
+                        .or_else(|| guild.members.get(&state.user_id));
+                    !matches!(member, Some(member) if member.user.bot)
+                })
+                .map(|state| state.user_id)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Create a channel for `member` and move them into it when `channel_id` is a hub
+pub async fn joined(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    member: &Member,
+    channel_id: ChannelId,
+) -> Result<(), TempVoiceError> {
+    let hubs = store.list_voice_hubs(guild_id).await?;
+    if !hubs.contains(&channel_id.to_string()) {
+        return Ok(());
+    }
+
+    // Created next to the hub, so it picks up the permissions of the category
+    let parent_id = ctx
+        .cache
+        .guild_channel_field(channel_id, |hub| hub.parent_id)
+        .flatten();
+    let name = channel_name(ctx, store, guild_id, member).await;
+
+    let channel = guild_id
+        .create_channel(&ctx.http, |c| {
+            c.name(&name).kind(ChannelType::Voice);
+            if let Some(parent_id) = parent_id {
+                c.category(parent_id);
+            }
+            c
+        })
+        .await
+        .map_err(|error| TempVoiceError::Other(error.to_string()))?;
+
+    // Stored before the move, so a restart in between still cleans the channel up
+    store
+        .set_temp_voice_channel(guild_id, channel.id.to_string(), member.user.id.to_string())
+        .await?;
+
+    if let Err(error) = guild_id
+        .move_member(&ctx.http, member.user.id, channel.id)
+        .await
+    {
+        // They left the hub before the channel was ready
+        debug!(
+            "Could not move {} to {}: {}",
+            member.user.id, channel.id, error
+        );
+        delete(ctx, store, guild_id, channel.id).await?;
+    }
+
+    Ok(())
+}
+
+/// Delete `channel_id` once the last member left, or hand it over when the owner left
+pub async fn left(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: UserId,
+    channel_id: ChannelId,
+) -> Result<(), TempVoiceError> {
+    let owner_id = match temp_channels(store, guild_id).await?.get(&channel_id) {
+        Some(owner_id) => *owner_id,
+        None => return Ok(()),
+    };
+
+    // A channel left to bots is as good as empty
+    let members = members_in(ctx, guild_id, channel_id);
+    let new_owner = match members.first() {
+        Some(new_owner) => *new_owner,
+        None => return delete(ctx, store, guild_id, channel_id).await,
+    };
+
+    if owner_id == user_id {
+        transfer(ctx, store, guild_id, channel_id, owner_id, new_owner).await?;
+    }
+
+    Ok(())
+}
+
+/// Hand `channel_id` from `owner_id` to `new_owner`, along with the owner's way into a locked channel
+pub async fn transfer(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    owner_id: UserId,
+    new_owner: UserId,
+) -> Result<(), TempVoiceError> {
+    store
+        .set_temp_voice_channel(guild_id, channel_id.to_string(), new_owner.to_string())
+        .await?;
+
+    let (owner_overwrite, locked) = ctx
+        .cache
+        .guild_channel_field(channel_id, |channel| {
+            let overwrites = &channel.permission_overwrites;
+            let owner_overwrite = overwrites
+                .iter()
+                .any(|overwrite| overwrite.kind == PermissionOverwriteType::Member(owner_id));
+            let locked = overwrites.iter().any(|overwrite| {
+                overwrite.kind == PermissionOverwriteType::Role(RoleId(guild_id.0))
+                    && overwrite.deny.contains(Permissions::CONNECT)
+            });
+            (owner_overwrite, locked)
+        })
+        .unwrap_or((false, false));
+
+    if owner_overwrite {
+        channel_id
+            .delete_permission(&ctx.http, PermissionOverwriteType::Member(owner_id))
+            .await
+            .map_err(|error| TempVoiceError::Other(error.to_string()))?;
+    }
+
+    if locked {
+        channel_id
+            .create_permission(&ctx.http, &owner_access(new_owner))
+            .await
+            .map_err(|error| TempVoiceError::Other(error.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Clean up after the bot was offline: forget deleted channels and delete the empty ones
+pub async fn reconcile(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild: &Guild,
+) -> Result<(), TempVoiceError> {
+    for channel_id in temp_channels(store, guild.id).await?.into_keys() {
+        let in_use = guild
+            .voice_states
+            .values()
+            .any(|state| state.channel_id == Some(channel_id));
+
+        if !guild.channels.contains_key(&channel_id) {
+            store
+                .remove_temp_voice_channel(guild.id, channel_id.to_string())
+                .await?;
+        } else if !in_use {
+            info!("Deleting temporary voice channel {} left empty", channel_id);
+            delete(ctx, store, guild.id, channel_id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn delete(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<(), TempVoiceError> {
+    if let Err(error) = channel_id.delete(&ctx.http).await {
+        let already_deleted = match &error {
+            serenity::Error::Http(error) => {
+                error.status_code().map(|status| status.as_u16()) == Some(404)
+            }
+            _ => false,
+        };
+
+        // Kept in the store so the next start up tries again
+        if !already_deleted {
+            return Err(TempVoiceError::Other(error.to_string()));
+        }
+    }
+
+    store
+        .remove_temp_voice_channel(guild_id, channel_id.to_string())
+        .await?;
+    Ok(())
+}
+
+// The game role of what `member` is playing, otherwise their name
+async fn channel_name(
+    ctx: &Context,
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    member: &Member,
+) -> String {
+    let playing: Vec<String> = ctx
+        .cache
+        .guild_field(guild_id, |guild| {
+            guild.presences.get(&member.user.id).map(|presence| {
+                presence
+                    .activities
+                    .iter()
+                    .filter(|activity| activity.kind == ActivityType::Playing)
+                    .map(|activity| activity.name.clone())
+                    .collect()
+            })
+        })
+        .flatten()
+        .unwrap_or_default();
+
+    let games = store.list_games(guild_id).await.unwrap_or_default();
+    for role_id in &member.roles {
+        if !games.contains(&role_id.to_string()) {
+            continue;
+        }
+
+        if let Some(role) = ctx.cache.role(guild_id, *role_id) {
+            if playing
+                .iter()
+                .any(|game| game.eq_ignore_ascii_case(&role.name))
+            {
+                return role.name;
+            }
+        }
+    }
+
+    format!("{}'s channel", member.display_name())
+}
+
+/// Stop or allow everyone joining `channel_id`, members already in it stay
+pub async fn set_locked(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    owner_id: UserId,
+    locked: bool,
+) -> serenity::Result<()> {
+    // @everyone shares its id with the guild
+    let everyone = PermissionOverwriteType::Role(RoleId(guild_id.0));
+
+    // Keep whatever else the overwrite synced from the category says
+    let mut overwrite = ctx
+        .cache
+        .guild_channel_field(channel_id, |channel| {
+            channel
+                .permission_overwrites
+                .iter()
+                .find(|overwrite| overwrite.kind == everyone)
+                .cloned()
+        })
+        .flatten()
+        .unwrap_or(PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::empty(),
+            kind: everyone,
+        });
+
+    if locked {
+        overwrite.allow.remove(Permissions::CONNECT);
+        overwrite.deny.insert(Permissions::CONNECT);
+        channel_id.create_permission(&ctx.http, &overwrite).await?;
+
+        // The owner can still come back
+        channel_id
+            .create_permission(&ctx.http, &owner_access(owner_id))
+            .await
+    } else {
+        if !overwrite.deny.contains(Permissions::CONNECT) {
+            return Ok(());
+        }
+
+        overwrite.deny.remove(Permissions::CONNECT);
+        if overwrite.allow.is_empty() && overwrite.deny.is_empty() {
+            channel_id.delete_permission(&ctx.http, everyone).await
+        } else {
+            channel_id.create_permission(&ctx.http, &overwrite).await
+        }
+    }
+}
+
+fn owner_access(owner_id: UserId) -> PermissionOverwrite {
+    PermissionOverwrite {
+        allow: Permissions::CONNECT,
+        deny: Permissions::empty(),
+        kind: PermissionOverwriteType::Member(owner_id),
+    }
+}
+
+/// Disconnect `user_id` from `channel_id` and keep them from coming back
+pub async fn kick(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> serenity::Result<()> {
+    channel_id
+        .create_permission(
+            &ctx.http,
+            &PermissionOverwrite {
+                allow: Permissions::empty(),
+                deny: Permissions::CONNECT,
+                kind: PermissionOverwriteType::Member(user_id),
+            },
+        )
+        .await?;
+
+    guild_id.disconnect_member(&ctx.http, user_id).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renames_are_limited_per_channel() {
+        let channel_id = ChannelId(1);
+        for _ in 0..RENAMES_PER_WINDOW {
+            assert!(claim_rename(channel_id).is_ok());
+        }
+
+        let wait = claim_rename(channel_id).unwrap_err();
+        assert!(wait > Duration::ZERO && wait <= RENAME_WINDOW);
+        assert!(claim_rename(ChannelId(2)).is_ok());
+    }
+}
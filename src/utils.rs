@@ -1,9 +1,25 @@
+/// Log an error as coming from the module it happened in, so it can be filtered with `LOG_FILTER`
+#[macro_export]
+macro_rules! log_error {
+    ($error:expr) => {
+        ::tracing::error!("{}", $error)
+    };
+}
+
 pub mod logging {
-    use std::error::Error;
+    use std::env;
+    use tracing_subscriber::EnvFilter;
+
+    /// Start logging, filtered by `LOG_FILTER` (e.g. `info,mythicc_bot::events::voice_state_update=warn`)
+    /// and printed as JSON when `LOG_FORMAT=json`
+    pub fn init() {
+        let filter = EnvFilter::try_from_env("LOG_FILTER")
+            .unwrap_or_else(|_| EnvFilter::new("warn,mythicc_bot=info"));
+        let subscriber = tracing_subscriber::fmt().with_env_filter(filter);
 
-    pub fn log_error(error: &impl Error) {
-        if error.to_string() != "" {
-            println!("{}", error);
+        match env::var("LOG_FORMAT").as_deref() {
+            Ok("json") => subscriber.json().init(),
+            _ => subscriber.init(),
         }
     }
 }
@@ -0,0 +1,309 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serenity::model::guild::Guild;
+use serenity::model::id::{ChannelId, GuildId, UserId};
+use std::collections::HashMap;
+use tracing::debug;
+
+use crate::store::errors::StoreError;
+use crate::store::BotStore;
+
+/// Finished sessions kept per user, the oldest are dropped first
+pub const MAX_SESSIONS: usize = 200;
+/// History is dropped once a user hasn't been in voice for this long,
+/// it has to cover a month for the monthly total
+pub const RETENTION_DAYS: i64 = 35;
+
+const WEEK_SECS: i64 = 7 * 24 * 60 * 60;
+const MONTH_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Time a user spent in voice, from joining a channel to leaving the last one
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VoiceSession {
+    pub started_at: i64,
+    pub ended_at: Option<i64>,
+    /// Every channel of the session in the order they were joined
+    pub channels: Vec<ChannelId>,
+    /// The bot missed the leave, `ended_at` is when it noticed
+    #[serde(default)]
+    pub interrupted: bool,
+}
+
+impl VoiceSession {
+    fn new(channel_id: ChannelId) -> Self {
+        Self {
+            started_at: Utc::now().timestamp(),
+            ended_at: None,
+            channels: vec![channel_id],
+            interrupted: false,
+        }
+    }
+
+    /// Seconds in voice, up to now for a session that is still going
+    pub fn duration(&self) -> i64 {
+        let ended_at = self.ended_at.unwrap_or_else(|| Utc::now().timestamp());
+        (ended_at - self.started_at).max(0)
+    }
+
+    // Seconds of the session that fall after `since`
+    fn duration_since(&self, since: i64) -> i64 {
+        let ended_at = self.ended_at.unwrap_or_else(|| Utc::now().timestamp());
+        (ended_at - self.started_at.max(since)).max(0)
+    }
+}
+
+/// Time in voice over the last 7 and 30 days, in seconds
+pub struct VoiceTotals {
+    pub week: i64,
+    pub month: i64,
+}
+
+impl VoiceTotals {
+    pub fn new<'a>(sessions: impl IntoIterator<Item = &'a VoiceSession>) -> Self {
+        let now = Utc::now().timestamp();
+        let mut totals = VoiceTotals { week: 0, month: 0 };
+
+        for session in sessions {
+            totals.week += session.duration_since(now - WEEK_SECS);
+            totals.month += session.duration_since(now - MONTH_SECS);
+        }
+
+        totals
+    }
+}
+
+/// e.g. "2h13m", "45m" or "30s"
+pub fn format_duration(secs: i64) -> String {
+    let hours = secs / 3600;
+    let minutes = (secs % 3600) / 60;
+
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", secs),
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h{}m", hours, minutes),
+    }
+}
+
+fn parse(session: &str) -> Option<VoiceSession> {
+    match serde_json::from_str(session) {
+        Ok(session) => Some(session),
+        Err(error) => {
+            debug!("Skipping a broken voice session: {}", error);
+            None
+        }
+    }
+}
+
+fn to_json(session: &VoiceSession) -> String {
+    // Only plain numbers and ids, serializing can't fail
+    serde_json::to_string(session).unwrap_or_default()
+}
+
+/// The session `user_id` is in right now, if any
+pub async fn open_session(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<VoiceSession>, StoreError> {
+    let session = store
+        .get_open_voice_session(guild_id, user_id.to_string())
+        .await?;
+    Ok(session.as_deref().and_then(parse))
+}
+
+/// Finished sessions of `user_id`, newest first
+pub async fn history(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Vec<VoiceSession>, StoreError> {
+    let sessions = store
+        .list_voice_history(guild_id, user_id.to_string())
+        .await?;
+    Ok(sessions
+        .iter()
+        .filter_map(|session| parse(session))
+        .collect())
+}
+
+async fn finish(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: UserId,
+    mut session: VoiceSession,
+    interrupted: bool,
+) -> Result<VoiceSession, StoreError> {
+    session.ended_at = Some(Utc::now().timestamp());
+    session.interrupted = interrupted;
+
+    store
+        .add_voice_history(
+            guild_id,
+            user_id.to_string(),
+            to_json(&session),
+            MAX_SESSIONS,
+            (RETENTION_DAYS * 24 * 60 * 60) as usize,
+        )
+        .await?;
+    store
+        .remove_open_voice_session(guild_id, user_id.to_string())
+        .await?;
+
+    Ok(session)
+}
+
+/// Start a session when `user_id` joins voice
+pub async fn joined(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: UserId,
+    channel_id: ChannelId,
+) -> Result<(), StoreError> {
+    // The leave of the previous session was missed
+    if let Some(session) = open_session(store, guild_id, user_id).await? {
+        finish(store, guild_id, user_id, session, true).await?;
+    }
+
+    store
+        .set_open_voice_session(
+            guild_id,
+            user_id.to_string(),
+            to_json(&VoiceSession::new(channel_id)),
+        )
+        .await
+}
+
+/// Add `channel_id` to the session of `user_id`
+pub async fn moved(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: UserId,
+    channel_id: ChannelId,
+) -> Result<(), StoreError> {
+    let session = match open_session(store, guild_id, user_id).await? {
+        Some(mut session) => {
+            session.channels.push(channel_id);
+            session
+        }
+        // The join was missed, the session starts here
+        None => VoiceSession::new(channel_id),
+    };
+
+    store
+        .set_open_voice_session(guild_id, user_id.to_string(), to_json(&session))
+        .await
+}
+
+/// End the session of `user_id`, `None` when the join was missed
+pub async fn left(
+    store: &dyn BotStore,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Option<VoiceSession>, StoreError> {
+    match open_session(store, guild_id, user_id).await? {
+        Some(session) => Ok(Some(
+            finish(store, guild_id, user_id, session, false).await?,
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Match the stored sessions to who is in voice now, after the bot was offline.
+///
+/// Sessions of members who left in the meantime end now and are marked as interrupted,
+/// members already in voice start a session now
+pub async fn reconcile(store: &dyn BotStore, guild: &Guild) -> Result<(), StoreError> {
+    let in_voice: HashMap<UserId, ChannelId> = guild
+        .voice_states
+        .values()
+        .filter_map(|state| {
+            state
+                .channel_id
+                .map(|channel_id| (state.user_id, channel_id))
+        })
+        .collect();
+
+    let open = store.list_open_voice_sessions(guild.id).await?;
+    for (user_id, session) in &open {
+        let (user_id, session) = match (user_id.parse::<u64>(), parse(session)) {
+            (Ok(user_id), Some(session)) => (UserId(user_id), session),
+            _ => {
+                store
+                    .remove_open_voice_session(guild.id, user_id.clone())
+                    .await?;
+                continue;
+            }
+        };
+
+        if !in_voice.contains_key(&user_id) {
+            finish(store, guild.id, user_id, session, true).await?;
+        }
+    }
+
+    for (user_id, channel_id) in in_voice {
+        if !open.contains_key(&user_id.to_string()) {
+            store
+                .set_open_voice_session(
+                    guild.id,
+                    user_id.to_string(),
+                    to_json(&VoiceSession::new(channel_id)),
+                )
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::memory_store::MemoryStore;
+
+    const GUILD: GuildId = GuildId(1);
+    const USER: UserId = UserId(10);
+    const LOBBY: ChannelId = ChannelId(20);
+    const GAMING: ChannelId = ChannelId(21);
+
+    #[tokio::test]
+    async fn a_session_follows_the_user_until_they_leave() {
+        let store = MemoryStore::default();
+
+        joined(&store, GUILD, USER, LOBBY).await.unwrap();
+        moved(&store, GUILD, USER, GAMING).await.unwrap();
+        let session = left(&store, GUILD, USER).await.unwrap().unwrap();
+
+        assert_eq!(session.channels, vec![LOBBY, GAMING]);
+        assert!(session.ended_at.is_some());
+        assert!(!session.interrupted);
+        assert!(open_session(&store, GUILD, USER).await.unwrap().is_none());
+        assert_eq!(history(&store, GUILD, USER).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_missed_leave_is_recorded_as_interrupted() {
+        let store = MemoryStore::default();
+
+        joined(&store, GUILD, USER, LOBBY).await.unwrap();
+        joined(&store, GUILD, USER, GAMING).await.unwrap();
+
+        let sessions = history(&store, GUILD, USER).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].interrupted);
+        assert_eq!(
+            open_session(&store, GUILD, USER)
+                .await
+                .unwrap()
+                .map(|session| session.channels),
+            Some(vec![GAMING])
+        );
+    }
+
+    #[tokio::test]
+    async fn leaving_without_a_join_records_nothing() {
+        let store = MemoryStore::default();
+
+        assert!(left(&store, GUILD, USER).await.unwrap().is_none());
+        assert!(history(&store, GUILD, USER).await.unwrap().is_empty());
+    }
+}